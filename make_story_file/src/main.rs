@@ -3,6 +3,17 @@ use std::{
     io::{stdin, stdout, Read, Write},
 };
 
+/// same as story_lib::story_base::derive_line_id with the title as chapter, and repeats get the
+/// same -2, -3 .. suffix, so the ids match the ones StoryPage{chapter: title} would assign
+fn line_id(chapter: &str, speaker: &str, text: &str) -> String {
+    let hash = format!("{chapter}\n{speaker}\n{text}")
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+    format!("{hash:016x}")
+}
+
 fn main() {
     // todo : 다음과 같은 형식의 메세지를 rust 코드로 변환
     // input
@@ -18,9 +29,9 @@ fn main() {
     //    let 등장인물1_color = "input color";
     //    let 등장인물2_color = "input color";
     //    vec![
-    //        base_story.clone().msg(TextPrint::parse(format!("{{{{}}}}히오스를 좋아하는 두 친구가 대화한다."))),
-    //        등장인물1_story.clone().msg(TextPrint::parse(format!("{{{{color:{등장인물1_color}}}}}안녕?"))),
-    //        등장인물2_story.clone().msg(TextRpint::parse(format!("{{{{color:{등장인물2_color}}}}}잘지내?"))),
+    //        base_story.clone().msg(TextPrint::parse(format!("{{{{}}}}히오스를 좋아하는 두 친구가 대화한다."))).id("..."),
+    //        등장인물1_story.clone().msg(TextPrint::parse(format!("{{{{color:{등장인물1_color}}}}}안녕?"))).id("..."),
+    //        등장인물2_story.clone().msg(TextRpint::parse(format!("{{{{color:{등장인물2_color}}}}}잘지내?"))).id("..."),
     //    ]
     // }
 
@@ -31,6 +42,18 @@ fn main() {
     let title = input.next().unwrap().trim().replace(" ", "_");
 
     let mut characters = HashSet::new();
+    let mut ids = HashSet::new();
+    let mut unique_id = |speaker: &str, text: &str| {
+        let base = line_id(&title, speaker, text);
+        let mut id = base.clone();
+        let mut count = 1;
+        while ids.contains(&id) {
+            count += 1;
+            id = format!("{base}-{count}");
+        }
+        ids.insert(id.clone());
+        id
+    };
 
     let mut after_output = String::from("vec![\n");
     while let Some(script) = input.next() {
@@ -42,16 +65,18 @@ fn main() {
             let name = script[0].trim();
             let value = script[1].trim();
             characters.insert(name);
+            let id = unique_id(name, value);
             after_output.push_str(
                 format!(
-                    "\t\t{name}_story.clone().msg(TextPrint::parse(format!(\"{{{{{{{{color:{{{name}_color}}}}}}}}}}{value}\"))),\n"
+                    "\t\t{name}_story.clone().msg(TextPrint::parse(format!(\"{{{{{{{{color:{{{name}_color}}}}}}}}}}{value}\"))).id(\"{id}\"),\n"
                 )
                 .as_str(),
             );
         } else {
             let value = script[0];
+            let id = unique_id("", value);
             after_output.push_str(
-                format!("\t\tbase_story.clone().msg(TextPrint::parse(format!(\"{{{{{{{{}}}}}}}}{value}\"))),\n").as_str(),
+                format!("\t\tbase_story.clone().msg(TextPrint::parse(format!(\"{{{{{{{{}}}}}}}}{value}\"))).id(\"{id}\"),\n").as_str(),
             );
         }
     }
@@ -71,3 +96,14 @@ fn main() {
         .write_all(format!("{before_output}{after_output}").as_bytes())
         .unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::line_id;
+
+    #[test]
+    fn line_id_matches_story_lib() {
+        // the same value is checked against derive_line_id in story_lib
+        assert_eq!(line_id("prologue", "alice", "hello"), "f2ae65c5bee0729c");
+    }
+}
@@ -0,0 +1,240 @@
+use dioxus::prelude::*;
+
+use crate::sprite::Edge;
+use crate::story_base::{use_box_state, wait};
+
+/// how the background changes to the next one
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum BackgroundTransition {
+    #[default]
+    None,
+    Crossfade,
+    /// the new background is revealed starting from the edge
+    Wipe(Edge),
+    /// crossfade with the old background blurring away
+    Dissolve,
+    FadeThroughBlack,
+}
+
+/// # Background
+/// A story background with an image or color and the transition used when it appears
+/// ## Example
+/// ```
+/// fn test() {
+///     let story = Story::default().backdrop(
+///         Background::image("image/school.webp").transition(BackgroundTransition::Crossfade, 800),
+///     );
+///     assert_eq!(story.background.image.as_deref(), Some("image/school.webp"));
+/// }
+/// ```
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Background {
+    /// raw style of the main element
+    pub style: String,
+    pub image: Option<String>,
+    pub color: Option<String>,
+    pub transition: BackgroundTransition,
+    /// miliseconds
+    pub duration: u32,
+}
+impl Background {
+    pub fn image(image: &str) -> Self {
+        Background {
+            image: Some(image.to_owned()),
+            ..Default::default()
+        }
+    }
+    pub fn color(color: &str) -> Self {
+        Background {
+            color: Some(color.to_owned()),
+            ..Default::default()
+        }
+    }
+    pub fn style(mut self, style: &str) -> Self {
+        self.style = style.to_owned();
+        self
+    }
+    pub fn transition(mut self, transition: BackgroundTransition, duration: u32) -> Self {
+        self.transition = transition;
+        self.duration = duration;
+        self
+    }
+    /// same picture on screen, the transition settings are not compared
+    pub fn same_view(&self, other: &Background) -> bool {
+        self.image == other.image && self.color == other.color
+    }
+    fn layer_style(&self) -> String {
+        let mut style = "position: fixed;inset: 0;".to_owned();
+        if let Some(color) = &self.color {
+            style.push_str(&format!("background-color: {color};"));
+        }
+        if let Some(image) = &self.image {
+            style.push_str(&format!(
+                r#"background-image: url("{image}");background-size: cover;background-position: center;"#
+            ));
+        }
+        style
+    }
+}
+impl From<&str> for Background {
+    fn from(style: &str) -> Self {
+        Background::default().style(style)
+    }
+}
+impl From<String> for Background {
+    fn from(style: String) -> Self {
+        Background {
+            style,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Stage {
+    Idle,
+    /// the old background is drawn untouched for one frame
+    Start,
+    /// the old background is animating away
+    Run,
+    /// fade through black only, the black cover is fading out
+    Reveal,
+}
+
+fn outgoing_style(transition: BackgroundTransition, stage: Stage, duration: u32) -> String {
+    let running = stage == Stage::Run;
+    match transition {
+        BackgroundTransition::None | BackgroundTransition::FadeThroughBlack => "".to_owned(),
+        BackgroundTransition::Crossfade => {
+            let opacity = if running { 0 } else { 1 };
+            format!("transition: opacity {duration}ms;opacity: {opacity};")
+        }
+        BackgroundTransition::Dissolve => {
+            let (opacity, blur) = if running { (0, 12) } else { (1, 0) };
+            format!("transition: opacity {duration}ms, filter {duration}ms;opacity: {opacity};filter: blur({blur}px);")
+        }
+        BackgroundTransition::Wipe(edge) => {
+            let clip = match (running, edge) {
+                (false, _) => "0 0 0 0",
+                (true, Edge::Left) => "0 0 0 100%",
+                (true, Edge::Right) => "0 100% 0 0",
+                (true, Edge::Top) => "100% 0 0 0",
+                (true, Edge::Bottom) => "0 0 100% 0",
+            };
+            format!("transition: clip-path {duration}ms;clip-path: inset({clip});")
+        }
+    }
+}
+
+fn cover_style(stage: Stage, duration: u32) -> String {
+    let opacity = if stage == Stage::Run { 1 } else { 0 };
+    format!(
+        "position: fixed;inset: 0;background-color: black;pointer-events: none;transition: opacity {}ms;opacity: {opacity};",
+        duration / 2
+    )
+}
+
+/// Draws the background image or color and keeps the old one on screen until the transition ends.
+/// The raw style is not drawn here, StoryPage puts it on the main element.
+#[component]
+pub fn BackgroundLayer(background: Background) -> Element {
+    let state = use_box_state();
+    let mut shown = use_signal(|| background.clone());
+    let mut outgoing = use_signal(|| None::<Background>);
+    let mut stage = use_signal(|| Stage::Idle);
+    let mut transition_count = use_signal(|| 0_usize);
+
+    use_effect(use_reactive((&background,), move |(background,)| {
+        if shown.peek().same_view(&background) {
+            *shown.write() = background;
+            return;
+        }
+        let before = shown.replace(background.clone());
+        if background.transition == BackgroundTransition::None
+            || background.duration == 0
+            || state.peek().is_ffward
+        {
+            *outgoing.write() = None;
+            *stage.write() = Stage::Idle;
+            return;
+        }
+        *outgoing.write() = Some(before);
+        *stage.write() = Stage::Start;
+        *transition_count.write() += 1;
+        let count = *transition_count.peek();
+        spawn(async move {
+            wait(16).await;
+            if *transition_count.peek() != count {
+                return;
+            }
+            *stage.write() = Stage::Run;
+            if background.transition == BackgroundTransition::FadeThroughBlack {
+                wait(background.duration / 2).await;
+                if *transition_count.peek() != count {
+                    return;
+                }
+                *outgoing.write() = None;
+                *stage.write() = Stage::Reveal;
+                wait(background.duration / 2).await;
+            } else {
+                wait(background.duration).await;
+            }
+            if *transition_count.peek() == count {
+                *outgoing.write() = None;
+                *stage.write() = Stage::Idle;
+            }
+        });
+    }));
+
+    let current = shown();
+    rsx! {
+        if current.image.is_some() || current.color.is_some() {
+            div{
+                class: "story-background",
+                style: "{current.layer_style()}",
+            }
+        }
+        if let Some(before) = outgoing() {
+            div{
+                class: "story-background",
+                style: "{before.layer_style()}{outgoing_style(current.transition, stage(), current.duration)}",
+            }
+        }
+        if current.transition == BackgroundTransition::FadeThroughBlack && stage() != Stage::Idle {
+            div{
+                class: "story-background-cover",
+                style: "{cover_style(stage(), current.duration)}",
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{outgoing_style, Background, BackgroundTransition, Stage};
+    use crate::sprite::Edge;
+
+    #[test]
+    fn background_same_view() {
+        let school = Background::image("image/school.webp");
+        assert!(school.same_view(
+            &school
+                .clone()
+                .transition(BackgroundTransition::Crossfade, 500)
+        ));
+        assert!(!school.same_view(&Background::color("black")));
+    }
+
+    #[test]
+    fn wipe_clip() {
+        let wipe = BackgroundTransition::Wipe(Edge::Left);
+        assert_eq!(
+            outgoing_style(wipe, Stage::Start, 400),
+            "transition: clip-path 400ms;clip-path: inset(0 0 0 0);"
+        );
+        assert_eq!(
+            outgoing_style(wipe, Stage::Run, 400),
+            "transition: clip-path 400ms;clip-path: inset(0 0 0 100%);"
+        );
+    }
+}
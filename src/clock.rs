@@ -0,0 +1,280 @@
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use dioxus::prelude::*;
+
+/// Global variables for the clock used by wait(), see set_clock
+pub static CLOCK: GlobalSignal<Rc<dyn Clock>> = Signal::global(default_clock);
+
+fn default_clock() -> Rc<dyn Clock> {
+    Rc::new(RealClock::default())
+}
+
+/// time source of wait() and the StoryBox typewriter
+pub trait Clock {
+    /// miliseconds since the clock started
+    fn now(&self) -> u64;
+    /// resolves when now() has moved by mili
+    fn sleep(&self, mili: u32) -> Pin<Box<dyn Future<Output = ()>>>;
+}
+
+/// use the clock for wait() and StoryBox, e.g. a ManualClock in tests.
+/// a StoryBox on screen starts timing the line again with the new clock
+/// # Example
+/// ```
+/// fn test() {
+///     let clock = RealClock::default();
+///     set_clock(clock.clone());
+///     // the setting menu is open
+///     clock.pause();
+/// }
+/// ```
+pub fn set_clock(clock: impl Clock + 'static) {
+    *CLOCK.write() = Rc::new(clock);
+}
+
+/// a sleep of CLOCK, or of the real time outside a dioxus runtime where CLOCK can't be read
+pub(crate) fn clock_sleep(mili: u32) -> Pin<Box<dyn Future<Output = ()>>> {
+    if Runtime::current().is_some() {
+        CLOCK.peek().sleep(mili)
+    } else {
+        Box::pin(real_sleep(mili))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn real_sleep(mili: u32) {
+    use gloo_timers::future::TimeoutFuture;
+    TimeoutFuture::new(mili).await;
+}
+#[cfg(not(target_arch = "wasm32"))]
+async fn real_sleep(mili: u32) {
+    use std::time::Duration;
+    tokio::time::sleep(Duration::from_millis(mili as u64)).await;
+}
+
+#[cfg(target_arch = "wasm32")]
+type Instant = f64;
+#[cfg(target_arch = "wasm32")]
+fn instant_now() -> Instant {
+    js_sys::Date::now()
+}
+#[cfg(target_arch = "wasm32")]
+fn since(start: Instant) -> u64 {
+    (js_sys::Date::now() - start).max(0.) as u64
+}
+#[cfg(not(target_arch = "wasm32"))]
+type Instant = std::time::Instant;
+#[cfg(not(target_arch = "wasm32"))]
+fn instant_now() -> Instant {
+    std::time::Instant::now()
+}
+#[cfg(not(target_arch = "wasm32"))]
+fn since(start: Instant) -> u64 {
+    start.elapsed().as_millis() as u64
+}
+
+struct RealState {
+    start: Instant,
+    /// real time when paused
+    paused_at: Cell<Option<u64>>,
+    /// real time spent paused
+    paused_for: Cell<u64>,
+}
+
+/// # RealClock
+/// Timers of tokio on desktop and of the browser on wasm. While paused, now() stops
+/// and sleeps don't finish, so every wait() holds still.
+#[derive(Clone)]
+pub struct RealClock {
+    state: Rc<RealState>,
+}
+impl Default for RealClock {
+    fn default() -> Self {
+        RealClock {
+            state: Rc::new(RealState {
+                start: instant_now(),
+                paused_at: Cell::new(None),
+                paused_for: Cell::new(0),
+            }),
+        }
+    }
+}
+impl RealClock {
+    pub fn pause(&self) {
+        if self.state.paused_at.get().is_none() {
+            self.state.paused_at.set(Some(since(self.state.start)));
+        }
+    }
+    pub fn resume(&self) {
+        if let Some(at) = self.state.paused_at.take() {
+            let paused_for = self.state.paused_for.get() + since(self.state.start) - at;
+            self.state.paused_for.set(paused_for);
+        }
+    }
+    pub fn is_paused(&self) -> bool {
+        self.state.paused_at.get().is_some()
+    }
+}
+impl Clock for RealClock {
+    fn now(&self) -> u64 {
+        let real = self
+            .state
+            .paused_at
+            .get()
+            .unwrap_or_else(|| since(self.state.start));
+        real - self.state.paused_for.get()
+    }
+    fn sleep(&self, mili: u32) -> Pin<Box<dyn Future<Output = ()>>> {
+        let clock = self.clone();
+        let due = self.now() + mili as u64;
+        Box::pin(async move {
+            loop {
+                if clock.is_paused() {
+                    // checked once a frame until resumed
+                    real_sleep(16).await;
+                    continue;
+                }
+                let now = clock.now();
+                if now >= due {
+                    break;
+                }
+                real_sleep((due - now) as u32).await;
+            }
+        })
+    }
+}
+
+#[derive(Default)]
+struct ManualState {
+    now: u64,
+    /// the due time and waker of each pending sleep, by the id of the sleep
+    sleepers: BTreeMap<u64, (u64, Waker)>,
+    next_id: u64,
+}
+
+/// # ManualClock
+/// A clock that only moves with advance, for tests and tools that simulate a chapter
+/// ## Example
+/// ```
+/// fn test() {
+///     let clock = ManualClock::default();
+///     set_clock(clock.clone());
+///     // wait(1000) in the components finishes here, without waiting a second
+///     clock.advance(1000);
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct ManualClock {
+    state: Rc<RefCell<ManualState>>,
+}
+impl ManualClock {
+    /// move the time and wake the sleeps that are due
+    pub fn advance(&self, mili: u64) {
+        let due = {
+            let mut state = self.state.borrow_mut();
+            state.now += mili;
+            let now = state.now;
+            let (due, waiting) = std::mem::take(&mut state.sleepers)
+                .into_iter()
+                .partition::<BTreeMap<_, _>, _>(|(_, (at, _))| *at <= now);
+            state.sleepers = waiting;
+            due
+        };
+        for (_, waker) in due.into_values() {
+            waker.wake();
+        }
+    }
+}
+impl Clock for ManualClock {
+    fn now(&self) -> u64 {
+        self.state.borrow().now
+    }
+    fn sleep(&self, mili: u32) -> Pin<Box<dyn Future<Output = ()>>> {
+        let id = {
+            let mut state = self.state.borrow_mut();
+            state.next_id += 1;
+            state.next_id
+        };
+        Box::pin(ManualSleep {
+            state: Rc::clone(&self.state),
+            due: self.now() + mili as u64,
+            id,
+        })
+    }
+}
+
+struct ManualSleep {
+    state: Rc<RefCell<ManualState>>,
+    due: u64,
+    id: u64,
+}
+impl Future for ManualSleep {
+    type Output = ();
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.state.borrow_mut();
+        if state.now >= self.due {
+            state.sleepers.remove(&self.id);
+            Poll::Ready(())
+        } else {
+            // polled again, the newest waker replaces the old one
+            state
+                .sleepers
+                .insert(self.id, (self.due, cx.waker().clone()));
+            Poll::Pending
+        }
+    }
+}
+impl Drop for ManualSleep {
+    fn drop(&mut self) {
+        self.state.borrow_mut().sleepers.remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::{Context, Poll, Waker};
+
+    use super::{clock_sleep, Clock, ManualClock};
+
+    #[test]
+    fn manual_clock_sleep() {
+        let clock = ManualClock::default();
+        let mut sleep = clock.sleep(100);
+        let mut cx = Context::from_waker(Waker::noop());
+        assert_eq!(sleep.as_mut().poll(&mut cx), Poll::Pending);
+        clock.advance(99);
+        assert_eq!(sleep.as_mut().poll(&mut cx), Poll::Pending);
+        clock.advance(1);
+        assert_eq!(sleep.as_mut().poll(&mut cx), Poll::Ready(()));
+        assert_eq!(clock.now(), 100);
+    }
+
+    #[test]
+    fn manual_clock_sleepers() {
+        let clock = ManualClock::default();
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut sleep = clock.sleep(100);
+        for _ in 0..3 {
+            assert_eq!(sleep.as_mut().poll(&mut cx), Poll::Pending);
+        }
+        assert_eq!(clock.state.borrow().sleepers.len(), 1);
+
+        // a cancelled sleep leaves no waker behind
+        let mut other = clock.sleep(50);
+        assert_eq!(other.as_mut().poll(&mut cx), Poll::Pending);
+        drop(other);
+        drop(sleep);
+        assert!(clock.state.borrow().sleepers.is_empty());
+    }
+
+    #[test]
+    fn sleep_without_runtime() {
+        // CLOCK needs a dioxus runtime, the real time is used without one
+        let _sleep = clock_sleep(1);
+    }
+}
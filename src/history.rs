@@ -0,0 +1,202 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use dioxus::prelude::*;
+
+use crate::sound_effect::{SoundEffect, CURRENT_MUSIC};
+use crate::story_base::{StoryPosition, LOG};
+use crate::variable::{Variable, VARIABLES};
+
+/// the state when a line started, sprites and background come back with the story at the position
+#[derive(Clone, PartialEq, Debug)]
+pub struct Snapshot {
+    pub position: StoryPosition,
+    pub variables: BTreeMap<String, Variable>,
+    pub music: Option<String>,
+    /// MessageLog::end of LOG, later messages are removed on restore
+    pub log_end: usize,
+    pub choice_point: bool,
+}
+impl Snapshot {
+    pub fn capture(position: StoryPosition, choice_point: bool) -> Self {
+        Snapshot {
+            position,
+            variables: VARIABLES.peek().clone(),
+            music: CURRENT_MUSIC.peek().clone(),
+            log_end: LOG.peek().end(),
+            choice_point,
+        }
+    }
+    /// put variables, music and log back, the caller moves to the position
+    pub fn restore(&self) {
+        *VARIABLES.write() = self.variables.clone();
+        LOG.write().truncate(self.log_end);
+        if *CURRENT_MUSIC.peek() != self.music {
+            match &self.music {
+                Some(music) => {
+                    if let Err(e) = SoundEffect::play_music(music) {
+                        tracing::warn!("can't resume music {music}: {e}");
+                    }
+                }
+                None => SoundEffect::music_stop(),
+            }
+        }
+    }
+}
+
+/// # RollbackSettings
+/// Lets the player step back through the lines StoryPage has shown with the mouse wheel or PageUp
+/// ## Example
+/// ```
+/// fn test() {
+///     let rollback = RollbackSettings::default().limit(50).allow_choices(true);
+///     assert_eq!(rollback.limit, 50);
+/// }
+/// ```
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RollbackSettings {
+    /// the number of lines kept
+    pub limit: usize,
+    /// lines marked as Story::choice_point can be rolled back to and picked again
+    pub allow_choices: bool,
+}
+impl Default for RollbackSettings {
+    fn default() -> Self {
+        RollbackSettings {
+            limit: 100,
+            allow_choices: false,
+        }
+    }
+}
+impl RollbackSettings {
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+    pub fn allow_choices(mut self, allow_choices: bool) -> Self {
+        self.allow_choices = allow_choices;
+        self
+    }
+}
+
+/// # History
+/// Bounded list of snapshots of the lines StoryPage has shown, the last one is the current line
+/// ## Example
+/// ```
+/// fn test(first: Snapshot, second: Snapshot) {
+///     let mut history = History::new(100);
+///     history.push(first.clone());
+///     history.push(second);
+///     assert_eq!(history.rollback(true), Some(first));
+/// }
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct History {
+    entries: VecDeque<Snapshot>,
+    limit: usize,
+}
+impl History {
+    pub fn new(limit: usize) -> Self {
+        History {
+            entries: VecDeque::new(),
+            limit,
+        }
+    }
+    /// add the line just shown, a line shown again after rollback is not added twice
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self.entries.back().map(|s| &s.position) == Some(&snapshot.position) {
+            return;
+        }
+        self.entries.push_back(snapshot);
+        while self.entries.len() > self.limit.max(1) {
+            self.entries.pop_front();
+        }
+    }
+    /// drop the current line and give the one before it.
+    /// a choice point is not given back when allow_choices is false, so the choice can't be re-picked
+    pub fn rollback(&mut self, allow_choices: bool) -> Option<Snapshot> {
+        let target = self
+            .entries
+            .len()
+            .checked_sub(2)
+            .map(|i| &self.entries[i])?;
+        if target.choice_point && !allow_choices {
+            return None;
+        }
+        let target = target.clone();
+        self.entries.pop_back();
+        Some(target)
+    }
+    /// drop the lines after the position, everything is dropped when it is not in the history
+    pub fn rewind_to(&mut self, position: &StoryPosition) {
+        while self.entries.back().is_some_and(|s| &s.position != position) {
+            self.entries.pop_back();
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{History, Snapshot};
+    use crate::story_base::StoryPosition;
+    use crate::variable::Variable;
+
+    fn snapshot(index: usize, choice_point: bool) -> Snapshot {
+        Snapshot {
+            position: StoryPosition {
+                chapter: "prologue".to_owned(),
+                index,
+                line_id: format!("line-{index}"),
+            },
+            variables: BTreeMap::from([("count".to_owned(), Variable::Int(index as i64))]),
+            music: None,
+            log_end: index,
+            choice_point,
+        }
+    }
+
+    #[test]
+    fn history_rollback() {
+        let mut history = History::new(3);
+        for index in 0..5 {
+            history.push(snapshot(index, false));
+        }
+        history.push(snapshot(4, false));
+        assert_eq!(history.len(), 3);
+
+        assert_eq!(history.rollback(false), Some(snapshot(3, false)));
+        assert_eq!(history.rollback(false), Some(snapshot(2, false)));
+        assert_eq!(history.rollback(false), None);
+    }
+
+    #[test]
+    fn history_choice_point() {
+        let mut history = History::new(10);
+        history.push(snapshot(0, true));
+        history.push(snapshot(1, false));
+        assert_eq!(history.rollback(false), None);
+        assert_eq!(history.rollback(true), Some(snapshot(0, true)));
+    }
+
+    #[test]
+    fn history_rewind_to() {
+        let mut history = History::new(10);
+        for index in 0..5 {
+            history.push(snapshot(index, false));
+        }
+        history.rewind_to(&snapshot(2, false).position);
+        assert_eq!(history.len(), 3);
+        history.rewind_to(&snapshot(7, false).position);
+        assert!(history.is_empty());
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::runner::FFWARD_HOLD;
+
+/// what a key, the mouse wheel or the right button does in a StoryBox
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum InputAction {
+    /// finish the line, or go to the next one
+    Advance,
+    /// fast-forward while the key is held, the wheel and the right button turn it on or off
+    FastForward,
+    /// open the setting menu
+    Menu,
+    /// hide the message box until the screen is clicked
+    Hide,
+    Log,
+    /// turn auto on or off
+    Auto,
+    /// back a line, the log is opened when StoryPage has no RollbackSettings
+    Rollback,
+    QuickSave,
+    QuickLoad,
+}
+impl InputAction {
+    /// every action, in the order of the setting menu
+    pub const ALL: [InputAction; 9] = [
+        InputAction::Advance,
+        InputAction::FastForward,
+        InputAction::Menu,
+        InputAction::Hide,
+        InputAction::Log,
+        InputAction::Auto,
+        InputAction::Rollback,
+        InputAction::QuickSave,
+        InputAction::QuickLoad,
+    ];
+}
+impl Display for InputAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            InputAction::Advance => "Advance",
+            InputAction::FastForward => "Fast Forward",
+            InputAction::Menu => "Menu",
+            InputAction::Hide => "Hide",
+            InputAction::Log => "Log",
+            InputAction::Auto => "Auto",
+            InputAction::Rollback => "Rollback",
+            InputAction::QuickSave => "Quick Save",
+            InputAction::QuickLoad => "Quick Load",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// # KeyBindings
+/// The keys of StoryBox, kept in TEXTCONFIG and saved with the other settings.
+/// Keys are the names of KeyboardEvent::code, like "Enter", "KeyH" or "ControlLeft".
+/// Quick save and load only work when SaveSettings::shortcuts is on.
+/// ## Example
+/// ```
+/// fn test() {
+///     let bindings = KeyBindings::default()
+///         .bind("KeyS", InputAction::FastForward)
+///         .wheel_up(Some(InputAction::Log));
+///     assert_eq!(bindings.action("KeyS"), Some(InputAction::FastForward));
+///     TEXTCONFIG.write().key_bindings = bindings;
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct KeyBindings {
+    pub keys: BTreeMap<String, InputAction>,
+    #[serde(default)]
+    pub wheel_up: Option<InputAction>,
+    #[serde(default)]
+    pub right_click: Option<InputAction>,
+}
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let keys = [
+            ("Enter", InputAction::Advance),
+            ("NumpadEnter", InputAction::Advance),
+            ("Space", InputAction::Advance),
+            ("ControlLeft", InputAction::FastForward),
+            ("ControlRight", InputAction::FastForward),
+            ("Escape", InputAction::Menu),
+            ("KeyH", InputAction::Hide),
+            ("KeyL", InputAction::Log),
+            ("KeyA", InputAction::Auto),
+            ("PageUp", InputAction::Rollback),
+            ("F5", InputAction::QuickSave),
+            ("F9", InputAction::QuickLoad),
+        ]
+        .into_iter()
+        .map(|(key, action)| (key.to_owned(), action))
+        .collect();
+        KeyBindings {
+            keys,
+            wheel_up: Some(InputAction::Rollback),
+            right_click: Some(InputAction::Hide),
+        }
+    }
+}
+impl KeyBindings {
+    pub fn bind(mut self, key: &str, action: InputAction) -> Self {
+        self.keys.insert(key.to_owned(), action);
+        self
+    }
+    pub fn wheel_up(mut self, action: Option<InputAction>) -> Self {
+        self.wheel_up = action;
+        self
+    }
+    pub fn right_click(mut self, action: Option<InputAction>) -> Self {
+        self.right_click = action;
+        self
+    }
+    pub fn action(&self, key: &str) -> Option<InputAction> {
+        self.keys.get(key).copied()
+    }
+    /// the keys of the action, sorted
+    pub fn keys_of(&self, action: InputAction) -> Vec<&str> {
+        self.keys
+            .iter()
+            .filter(|(_, a)| **a == action)
+            .map(|(key, _)| key.as_str())
+            .collect()
+    }
+    /// make the key the only one of the action, the key stops doing what it did before
+    pub fn rebind(&mut self, action: InputAction, key: &str) {
+        self.keys.retain(|_, a| *a != action);
+        self.keys.insert(key.to_owned(), action);
+    }
+    pub fn unbind(&mut self, key: &str) {
+        self.keys.remove(key);
+    }
+}
+
+/// fingers moving less than this are still a tap, pixels
+pub const TAP_SLOP: f64 = 10.;
+/// a swipe has to move down this far, pixels
+pub const SWIPE_DISTANCE: f64 = 60.;
+
+/// what the fingers did on a StoryBox, a long press is the fast-forward hold
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Gesture {
+    /// advance
+    Tap,
+    /// open the log
+    SwipeDown,
+    /// hide the box
+    TwoFingerTap,
+}
+
+/// # TouchTracker
+/// Turns the touch events of a StoryBox into a Gesture, points are client coordinates
+/// ## Example
+/// ```
+/// fn test() {
+///     let mut tracker = TouchTracker::default();
+///     tracker.start(&[(100., 100.)], 0);
+///     tracker.moved(&[(102., 180.)]);
+///     assert_eq!(tracker.end(0, 200), Some(Gesture::SwipeDown));
+/// }
+/// ```
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TouchTracker {
+    start: Option<(f64, f64)>,
+    last: (f64, f64),
+    started_at: u64,
+    fingers: usize,
+    moved: bool,
+}
+impl TouchTracker {
+    /// fingers touched, points are all the touches now on the box
+    pub fn start(&mut self, points: &[(f64, f64)], now: u64) {
+        if self.start.is_none() {
+            self.start = points.first().copied();
+            self.last = points.first().copied().unwrap_or_default();
+            self.started_at = now;
+        }
+        self.fingers = self.fingers.max(points.len());
+    }
+    pub fn moved(&mut self, points: &[(f64, f64)]) {
+        if let (Some(start), Some(point)) = (self.start, points.first()) {
+            self.last = *point;
+            if (point.0 - start.0).hypot(point.1 - start.1) > TAP_SLOP {
+                self.moved = true;
+            }
+        }
+    }
+    /// fingers lifted, the gesture is given when the last one is lifted
+    pub fn end(&mut self, remaining: usize, now: u64) -> Option<Gesture> {
+        if remaining > 0 {
+            return None;
+        }
+        let touch = std::mem::take(self);
+        let start = touch.start?;
+        let (dx, dy) = (touch.last.0 - start.0, touch.last.1 - start.1);
+        if touch.moved {
+            (touch.fingers == 1 && dy >= SWIPE_DISTANCE && dy > dx.abs())
+                .then_some(Gesture::SwipeDown)
+        } else if now.saturating_sub(touch.started_at) > FFWARD_HOLD as u64 {
+            // the long press was a fast-forward
+            None
+        } else {
+            match touch.fingers {
+                1 => Some(Gesture::Tap),
+                2 => Some(Gesture::TwoFingerTap),
+                _ => None,
+            }
+        }
+    }
+    pub fn cancel(&mut self) {
+        *self = TouchTracker::default();
+    }
+    /// fingers are on the box
+    pub fn is_active(&self) -> bool {
+        self.start.is_some()
+    }
+    /// one finger that hasn't moved, holding it fast-forwards
+    pub fn is_press(&self) -> bool {
+        self.is_active() && self.fingers == 1 && !self.moved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gesture, InputAction, KeyBindings, TouchTracker};
+
+    #[test]
+    fn key_bindings_rebind() {
+        let mut bindings = KeyBindings::default();
+        assert_eq!(bindings.action("Space"), Some(InputAction::Advance));
+        assert_eq!(bindings.keys_of(InputAction::Menu), vec!["Escape"]);
+
+        bindings.rebind(InputAction::Log, "KeyA");
+        assert_eq!(bindings.keys_of(InputAction::Log), vec!["KeyA"]);
+        assert_eq!(bindings.keys_of(InputAction::Auto), Vec::<&str>::new());
+        assert_eq!(bindings.action("KeyL"), None);
+
+        let json = serde_json::to_string(&bindings).unwrap();
+        assert_eq!(
+            serde_json::from_str::<KeyBindings>(&json).unwrap(),
+            bindings
+        );
+    }
+
+    #[test]
+    fn touch_gestures() {
+        let mut tracker = TouchTracker::default();
+        tracker.start(&[(100., 100.)], 0);
+        tracker.moved(&[(104., 103.)]);
+        assert!(tracker.is_press());
+        assert_eq!(tracker.end(0, 150), Some(Gesture::Tap));
+        assert!(!tracker.is_active());
+
+        // the long press fast-forwarded
+        tracker.start(&[(100., 100.)], 0);
+        assert_eq!(tracker.end(0, 1500), None);
+
+        tracker.start(&[(100., 100.)], 0);
+        tracker.start(&[(100., 100.), (200., 100.)], 30);
+        assert!(!tracker.is_press());
+        assert_eq!(tracker.end(1, 100), None);
+        assert_eq!(tracker.end(0, 120), Some(Gesture::TwoFingerTap));
+
+        tracker.start(&[(100., 100.)], 0);
+        tracker.moved(&[(180., 130.)]);
+        assert_eq!(tracker.end(0, 200), None);
+    }
+}
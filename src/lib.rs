@@ -1,3 +1,15 @@
+pub mod background;
+pub mod clock;
+pub mod history;
+pub mod input;
+pub mod message_log;
+pub mod runner;
+pub mod save;
+pub mod scene;
+pub mod screen_effect;
 pub mod sound_effect;
+pub mod sprite;
 pub mod story_base;
 pub mod text_print;
+pub mod transcript;
+pub mod variable;
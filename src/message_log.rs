@@ -0,0 +1,494 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::error::Error;
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::history::Snapshot;
+use crate::save::{now, SavedText};
+use crate::sound_effect::SoundEffect;
+use crate::story_base::{use_box_state, LOG};
+use crate::text_print::TextPrint;
+
+/// a message in LOG
+#[derive(Clone, PartialEq, Debug)]
+pub struct LogEntry {
+    /// the speaker's name box
+    pub title: Vec<TextPrint>,
+    pub msg: Vec<TextPrint>,
+    /// see Story::line_id, empty when the box had no story
+    pub line_id: String,
+    /// unix time, seconds
+    pub timestamp: u64,
+    /// the option the player picked on this line, see record_choice
+    pub choice: Option<String>,
+    /// name in SOUND_EFFECTS of the voice clip, replayed from the log
+    pub voice: Option<String>,
+    /// state when the line started, a StoryPage can jump back to it
+    pub snapshot: Option<Snapshot>,
+}
+impl LogEntry {
+    pub fn new(title: Vec<TextPrint>, msg: Vec<TextPrint>) -> Self {
+        LogEntry {
+            title,
+            msg,
+            line_id: "".to_owned(),
+            timestamp: now(),
+            choice: None,
+            voice: None,
+            snapshot: None,
+        }
+    }
+    pub fn line_id(mut self, line_id: &str) -> Self {
+        self.line_id = line_id.to_owned();
+        self
+    }
+    pub fn choice(mut self, choice: &str) -> Self {
+        self.choice = Some(choice.to_owned());
+        self
+    }
+    pub fn voice(mut self, voice: Option<String>) -> Self {
+        self.voice = voice;
+        self
+    }
+    pub fn snapshot(mut self, snapshot: Option<Snapshot>) -> Self {
+        self.snapshot = snapshot;
+        self
+    }
+    /// the speaker's name as plain text
+    pub fn speaker(&self) -> String {
+        self.title
+            .iter()
+            .map(|t| t.msg.as_str())
+            .collect::<String>()
+            .trim()
+            .to_owned()
+    }
+    /// the message as plain text
+    pub fn text(&self) -> String {
+        self.msg.iter().map(|t| t.msg.as_str()).collect()
+    }
+    /// query is in the speaker, the message or the choice, ignoring case.
+    /// an empty speaker matches every speaker
+    pub fn matches(&self, query: &str, speaker: &str) -> bool {
+        if !speaker.is_empty() && self.speaker() != speaker {
+            return false;
+        }
+        let query = query.to_lowercase();
+        self.speaker().to_lowercase().contains(&query)
+            || self.text().to_lowercase().contains(&query)
+            || self
+                .choice
+                .as_ref()
+                .is_some_and(|c| c.to_lowercase().contains(&query))
+    }
+    pub fn play_voice(&self) {
+        if let Some(voice) = &self.voice {
+            if let Err(e) = SoundEffect::play_named(voice) {
+                tracing::warn!("can't play voice {voice}: {e}");
+            }
+        }
+    }
+}
+
+/// LogEntry without the snapshot and the closures of TextPrint, for save data and export
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SavedLogEntry {
+    #[serde(default)]
+    pub title: Vec<SavedText>,
+    pub msg: Vec<SavedText>,
+    #[serde(default)]
+    pub line_id: String,
+    #[serde(default)]
+    pub timestamp: u64,
+    #[serde(default)]
+    pub choice: Option<String>,
+    #[serde(default)]
+    pub voice: Option<String>,
+}
+impl From<&LogEntry> for SavedLogEntry {
+    fn from(entry: &LogEntry) -> Self {
+        SavedLogEntry {
+            title: entry.title.iter().map(SavedText::from).collect(),
+            msg: entry.msg.iter().map(SavedText::from).collect(),
+            line_id: entry.line_id.clone(),
+            timestamp: entry.timestamp,
+            choice: entry.choice.clone(),
+            voice: entry.voice.clone(),
+        }
+    }
+}
+impl SavedLogEntry {
+    pub fn to_log_entry(&self) -> LogEntry {
+        LogEntry {
+            title: self.title.iter().map(SavedText::to_text_print).collect(),
+            msg: self.msg.iter().map(SavedText::to_text_print).collect(),
+            line_id: self.line_id.clone(),
+            timestamp: self.timestamp,
+            choice: self.choice.clone(),
+            voice: self.voice.clone(),
+            snapshot: None,
+        }
+    }
+}
+
+/// # MessageLog
+/// The messages of LOG, the oldest are dropped past the limit
+/// ## Example
+/// ```
+/// fn test() {
+///     LOG.write().set_limit(200);
+///     let lines: Vec<String> = LOG.read().iter().map(|e| e.text()).collect();
+///     let json = LOG.read().to_json().unwrap();
+///     LOG.write().clear();
+/// }
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct MessageLog {
+    entries: VecDeque<LogEntry>,
+    limit: usize,
+    /// entries dropped from the front, so end() keeps counting
+    dropped: usize,
+}
+impl Default for MessageLog {
+    fn default() -> Self {
+        MessageLog::new(1000)
+    }
+}
+impl MessageLog {
+    pub fn new(limit: usize) -> Self {
+        MessageLog {
+            entries: VecDeque::new(),
+            limit,
+            dropped: 0,
+        }
+    }
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+    /// change the maximum length, 0 keeps nothing
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.trim();
+    }
+    pub fn push(&mut self, entry: LogEntry) {
+        self.entries.push_back(entry);
+        self.trim();
+    }
+    fn trim(&mut self) {
+        while self.entries.len() > self.limit {
+            self.entries.pop_front();
+            self.dropped += 1;
+        }
+    }
+    pub fn iter(&self) -> std::collections::vec_deque::Iter<'_, LogEntry> {
+        self.entries.iter()
+    }
+    pub fn get(&self, index: usize) -> Option<&LogEntry> {
+        self.entries.get(index)
+    }
+    pub fn last_mut(&mut self) -> Option<&mut LogEntry> {
+        self.entries.back_mut()
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.dropped += self.entries.len();
+        self.entries.clear();
+    }
+    /// the end() before the oldest entry kept was pushed, entry i stays start() + i when old ones are dropped
+    pub fn start(&self) -> usize {
+        self.dropped
+    }
+    /// the number of entries ever pushed, a Snapshot keeps it to cut the log back
+    pub fn end(&self) -> usize {
+        self.dropped + self.entries.len()
+    }
+    /// remove the entries pushed after end() returned end
+    pub fn truncate(&mut self, end: usize) {
+        self.entries.truncate(end.saturating_sub(self.dropped));
+    }
+    /// the entries pushed after end() returned end
+    pub fn since(&self, end: usize) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter().skip(end.saturating_sub(self.dropped))
+    }
+
+    pub fn export(&self) -> Vec<SavedLogEntry> {
+        self.entries.iter().map(SavedLogEntry::from).collect()
+    }
+    pub fn to_json(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string(&self.export())?)
+    }
+    /// replace the entries, e.g. with the log of save data
+    pub fn import(&mut self, entries: &[SavedLogEntry]) {
+        self.clear();
+        self.entries = entries.iter().map(SavedLogEntry::to_log_entry).collect();
+        self.trim();
+    }
+}
+
+/// remember the option picked at a choice point on the last log entry,
+/// call it from StoryPage::on_next, which runs after the line is logged
+/// # Example
+/// ```
+/// fn test() {
+///     record_choice("Go to the library");
+///     assert_eq!(LOG.read().iter().last().and_then(|e| e.choice.clone()), Some("Go to the library".to_owned()));
+/// }
+/// ```
+pub fn record_choice(option: &str) {
+    if let Some(entry) = LOG.write().last_mut() {
+        entry.choice = Some(option.to_owned());
+    }
+}
+
+/// entries drawn above and below the visible ones
+const OVERSCAN: usize = 5;
+
+/// The LOG view of StoryBox with search, a speaker filter and jump to top or bottom.
+/// Only the rows near the visible area are rendered, row_height is the estimated height in px.
+/// ## Example
+/// ```
+/// #[component]
+/// fn TestLog() -> Element {
+///     rsx! {
+///         MessageLogView{
+///             on_jump: move |snapshot: Snapshot| {
+///                 // optional, shows "jump here" on the entries
+///             },
+///             row_height: 96., // optional
+///         }
+///     }
+/// }
+/// ```
+#[component]
+pub fn MessageLogView(
+    on_jump: Option<EventHandler<Snapshot>>,
+    #[props(default = 96.)] row_height: f64,
+) -> Element {
+    let mut state = use_box_state();
+    let mut query = use_signal(String::new);
+    let mut speaker = use_signal(String::new);
+    let mut scroll_top = use_signal(|| 0_f64);
+    let mut view_height = use_signal(|| 800_f64);
+    let mut container = use_signal(|| None::<Rc<MountedData>>);
+    let mut list = use_signal(|| None::<Rc<MountedData>>);
+    let mut top = use_signal(|| None::<Rc<MountedData>>);
+    let mut bottom = use_signal(|| None::<Rc<MountedData>>);
+    let speakers = use_memo(move || {
+        LOG.read()
+            .iter()
+            .map(|e| e.speaker())
+            .filter(|s| !s.is_empty())
+            .collect::<BTreeSet<String>>()
+    });
+    // newest first, indices into LOG
+    let filtered = use_memo(move || {
+        let log = LOG.read();
+        (0..log.len())
+            .rev()
+            .filter(|&i| log.get(i).is_some_and(|e| e.matches(&query(), &speaker())))
+            .collect::<Vec<usize>>()
+    });
+    let measure = move || async move {
+        let (Some(container), Some(list)) = (container(), list()) else {
+            return;
+        };
+        if let (Ok(outer), Ok(inner)) = (
+            container.get_client_rect().await,
+            list.get_client_rect().await,
+        ) {
+            scroll_top.set(outer.origin.y - inner.origin.y);
+            view_height.set(outer.size.height);
+        }
+    };
+    let scroll_to = move |target: Signal<Option<Rc<MountedData>>>| async move {
+        if let Some(target) = target() {
+            let _ = target.scroll_to(ScrollBehavior::Instant).await;
+        }
+        measure().await;
+    };
+
+    let total = filtered.read().len();
+    let row_height = row_height.max(1.);
+    let first = ((scroll_top() / row_height) as usize)
+        .saturating_sub(OVERSCAN)
+        .min(total);
+    let last = (first + (view_height() / row_height).ceil() as usize + OVERSCAN * 2).min(total);
+    // keyed by the position in the whole log, it doesn't move when the limit drops old entries
+    let rows: Vec<(usize, LogEntry)> = {
+        let log = LOG.peek();
+        filtered.read()[first..last]
+            .iter()
+            .filter_map(|&i| log.get(i).cloned().map(|e| (log.start() + i, e)))
+            .collect()
+    };
+    let search = query();
+    rsx! {
+        section {
+            class: "message-log",
+            onscroll: move |_| measure(),
+            onmounted: move |e| container.set(Some(e.data())),
+            nav{
+                class: "msg-log-search",
+                onclick: move |e| e.stop_propagation(),
+                input{
+                    r#type: "search",
+                    placeholder: "search",
+                    value: "{search}",
+                    oninput: move |e| {
+                        query.set(e.value());
+                        scroll_top.set(0.);
+                    },
+                }
+                select{
+                    value: "{speaker}",
+                    onchange: move |e| {
+                        speaker.set(e.value());
+                        scroll_top.set(0.);
+                    },
+                    option{ value: "", "all" }
+                    for name in speakers().into_iter() {
+                        option{ value: "{name}", "{name}" }
+                    }
+                }
+                span{
+                    class: "msg-log-top",
+                    onclick: move |_| scroll_to(top),
+                    "top"
+                }
+                span{
+                    class: "msg-log-bottom",
+                    onclick: move |_| scroll_to(bottom),
+                    "bottom"
+                }
+            }
+            div{
+                onmounted: move |e| list.set(Some(e.data())),
+                div{
+                    onmounted: move |e| top.set(Some(e.data())),
+                    style: "height: {first as f64 * row_height}px;",
+                }
+                for (position, entry) in rows.into_iter() {
+                    article{
+                        key: "{position}",
+                        if !entry.title.is_empty(){
+                            div{
+                                class: "msg-log-title",
+                                for text in entry.title.iter(){
+                                    {text.highlight_print(&search)}
+                                }
+                            }
+                        }
+                        for text in entry.msg.iter(){
+                            {text.highlight_print(&search)}
+                        }
+                        if let Some(choice) = &entry.choice {
+                            div{
+                                class: "msg-log-choice",
+                                "{choice}"
+                            }
+                        }
+                        if entry.voice.is_some() {
+                            span{
+                                class: "msg-log-voice",
+                                onclick: {
+                                    let entry = entry.clone();
+                                    move |e: MouseEvent| {
+                                        entry.play_voice();
+                                        e.stop_propagation();
+                                    }
+                                },
+                                "voice"
+                            }
+                        }
+                        if let (Some(on_jump), Some(snapshot)) = (on_jump, entry.snapshot.clone()) {
+                            span{
+                                class: "msg-log-jump",
+                                onclick: move |e: MouseEvent| {
+                                    on_jump.call(snapshot.clone());
+                                    e.stop_propagation();
+                                },
+                                "jump here"
+                            }
+                        }
+                    }
+                }
+                div{
+                    style: "height: {(total - last) as f64 * row_height}px;",
+                }
+                div{
+                    onmounted: move |e| bottom.set(Some(e.data())),
+                }
+            }
+            nav{
+                class: "msg-log-button",
+                onclick: move |e| {
+                    state.write().is_log = false;
+                    e.stop_propagation();
+                },
+                "exit"
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LogEntry, MessageLog};
+    use crate::text_print::TextPrint;
+
+    fn entry(msg: &str) -> LogEntry {
+        LogEntry::new(vec![], vec![TextPrint::default().msg(msg)])
+    }
+
+    #[test]
+    fn message_log_limit() {
+        let mut log = MessageLog::new(3);
+        for i in 0..5 {
+            log.push(entry(&i.to_string()));
+        }
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.start(), 2);
+        assert_eq!(log.end(), 5);
+        assert_eq!(
+            log.since(3).map(|e| e.text()).collect::<Vec<_>>(),
+            ["3", "4"]
+        );
+
+        log.truncate(3);
+        assert_eq!(log.iter().map(|e| e.text()).collect::<Vec<_>>(), ["2"]);
+        log.truncate(1);
+        assert!(log.is_empty());
+
+        log.push(entry("a").choice("yes"));
+        let exported = log.export();
+        let mut imported = MessageLog::new(3);
+        imported.import(&exported);
+        assert_eq!(
+            imported.get(0).and_then(|e| e.choice.clone()),
+            Some("yes".to_owned())
+        );
+    }
+
+    #[test]
+    fn log_entry_matches() {
+        let line = LogEntry::new(
+            vec![TextPrint::default().msg("Alice")],
+            vec![
+                TextPrint::default().msg("Where is the "),
+                TextPrint::default().msg("Library?"),
+            ],
+        );
+        assert!(line.matches("the library", ""));
+        assert!(line.matches("alice", "Alice"));
+        assert!(line.matches("", "Alice"));
+        assert!(!line.matches("", "Bob"));
+        assert!(!line.matches("school", ""));
+    }
+}
@@ -0,0 +1,519 @@
+use crate::save::SaveError;
+use crate::text_print::TextPrint;
+
+/// holding the mouse or a finger this long turns on fast-forward, miliseconds
+pub const FFWARD_HOLD: u32 = 1000;
+/// miliseconds per character while fast-forwarding
+pub const FFWARD_SPEED: u32 = 5;
+/// a finished line stays this long while fast-forwarding, miliseconds
+pub const FFWARD_END: u32 = 10;
+
+/// why the runner moves to the next line
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AdvanceCause {
+    Click,
+    Auto,
+    Skip,
+    FastForward,
+}
+
+/// what happened during tick or advance, the view reacts to these
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RunnerEvent {
+    /// a character of the part at this index was shown
+    CharRevealed { part: usize },
+    /// every part is shown
+    LineCompleted,
+    /// the line is done, call on_next and then set_line with the next line
+    Advance(AdvanceCause),
+}
+
+/// # StoryEvent
+/// What happened in a StoryPage or LightMessageBox, given to on_event
+/// ## Example
+/// ```
+/// #[component]
+/// fn TestEvents() -> Element {
+///     rsx! {
+///         StoryPage{
+///             storys: vec![/*some story*/],
+///             next: rsx!{},
+///             on_next: move |_| {},
+///             skip_len: 0,
+///             skip: 0,
+///             other_setting: rsx!{},
+///             on_event: move |event: StoryEvent| {
+///                 if let StoryEvent::LineStarted { line_id, .. } = event {
+///                     // play the voice or unlock an achievement for line_id
+///                 }
+///             },
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum StoryEvent {
+    /// a line is shown, index in storys and Story::line_id
+    LineStarted {
+        index: usize,
+        line_id: String,
+    },
+    /// the number of characters of the line shown so far
+    CharRevealed {
+        index: usize,
+        chars: usize,
+    },
+    LineCompleted {
+        index: usize,
+    },
+    /// the line at index was left
+    Advanced {
+        index: usize,
+        cause: AdvanceCause,
+    },
+    LogOpened,
+    SettingsOpened,
+    /// a quick-load was refused, nothing was restored
+    LoadFailed(SaveError),
+    /// the last line was left
+    Finished,
+}
+
+/// # TickTimer
+/// The elapsed time given to StoryRunner::tick by a loop that sleeps on a Clock.
+/// The time while the loop is stopped is not counted, so a hold or auto after a pause
+/// starts from zero.
+/// ## Example
+/// ```
+/// async fn run(mut runner: StoryRunner) {
+///     let mut timer = TickTimer::default();
+///     while let Some(due) = runner.next_due() {
+///         wait(due).await;
+///         runner.tick(timer.elapsed(CLOCK.peek().now()));
+///     }
+///     timer.stop();
+/// }
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct TickTimer {
+    last: Option<u64>,
+}
+impl TickTimer {
+    /// miliseconds since the last call, 0 after stop
+    pub fn elapsed(&mut self, now: u64) -> u32 {
+        let elapsed = self.last.map_or(0, |last| now.saturating_sub(last));
+        self.last = Some(now);
+        elapsed.min(u32::MAX as u64) as u32
+    }
+    /// the loop went idle, or the line or the clock changed
+    pub fn stop(&mut self) {
+        self.last = None;
+    }
+}
+
+/// # StoryRunner
+/// The progression of StoryBox without Dioxus: typewriter, auto, fast-forward and skip.
+/// Time only moves with tick, so tests can drive it without a renderer.
+/// ## Example
+/// ```
+/// fn test() {
+///     let mut runner = StoryRunner::new(TextPrint::parse("{{speed:10}}hi".to_owned()), 0);
+///     runner.tick(20);
+///     assert_eq!(runner.visible_chars(), 2);
+///     runner.tick(10);
+///     assert!(runner.is_end());
+///     assert_eq!(runner.advance(), Some(RunnerEvent::Advance(AdvanceCause::Click)));
+/// }
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct StoryRunner {
+    text: Vec<TextPrint>,
+    story_index: usize,
+    skippable: bool,
+    skip_len: usize,
+    can_skip: bool,
+    /// text print speed, higher is faster, see TextConfig::speed
+    speed: f32,
+    /// see TextConfig::auto_speed
+    auto_speed: u32,
+    text_index: usize,
+    msg_index: usize,
+    /// miliseconds not spent on characters yet, or waited at the end of the line
+    budget: u32,
+    end: bool,
+    auto: bool,
+    ffward: bool,
+    skipping: bool,
+    holding: bool,
+    hold_time: u32,
+    /// an advance was sent and the next line hasn't been set
+    waiting: bool,
+}
+impl StoryRunner {
+    pub fn new(text: Vec<TextPrint>, story_index: usize) -> Self {
+        StoryRunner {
+            text,
+            story_index,
+            skippable: false,
+            skip_len: 0,
+            can_skip: true,
+            speed: 1.,
+            auto_speed: 5000,
+            text_index: 0,
+            msg_index: 0,
+            budget: 0,
+            end: false,
+            auto: false,
+            ffward: false,
+            skipping: false,
+            holding: false,
+            hold_time: 0,
+            waiting: false,
+        }
+    }
+    pub fn can_skip(mut self, can_skip: bool) -> Self {
+        self.can_skip = can_skip;
+        self
+    }
+    pub fn skip_len(mut self, skip_len: usize) -> Self {
+        self.skip_len = skip_len;
+        self
+    }
+    pub fn speed(mut self, speed: f32, auto_speed: u32) -> Self {
+        self.set_speed(speed, auto_speed);
+        self
+    }
+
+    /// show a line from the start
+    pub fn set_line(&mut self, text: Vec<TextPrint>, story_index: usize) {
+        self.text = text;
+        self.story_index = story_index;
+        self.text_index = 0;
+        self.msg_index = 0;
+        self.budget = 0;
+        self.end = false;
+        self.waiting = false;
+    }
+    /// the skip button may pass the line, see Story::is_skippable
+    pub fn set_skippable(&mut self, skippable: bool) {
+        self.skippable = skippable;
+    }
+    pub fn set_speed(&mut self, speed: f32, auto_speed: u32) {
+        self.speed = speed;
+        self.auto_speed = auto_speed;
+    }
+    /// the auto wait starts over when auto is turned on at the end of the line
+    pub fn set_auto(&mut self, auto: bool) {
+        if auto != self.auto && self.end {
+            self.budget = 0;
+        }
+        self.auto = auto;
+    }
+    pub fn set_ffward(&mut self, ffward: bool) {
+        self.ffward = ffward;
+    }
+
+    /// move the time forward by elapsed miliseconds
+    pub fn tick(&mut self, elapsed: u32) -> Vec<RunnerEvent> {
+        let mut events = vec![];
+        if self.waiting {
+            return events;
+        }
+        if self.holding {
+            self.hold_time += elapsed;
+            if self.hold_time > FFWARD_HOLD {
+                self.ffward = true;
+            }
+        }
+        if self.skipping {
+            if self.skip_len > self.story_index || self.skippable {
+                events.push(self.send_advance(AdvanceCause::Skip));
+                return events;
+            }
+            self.skipping = false;
+        }
+        self.budget = self.budget.saturating_add(elapsed);
+        while !self.text_done() {
+            let delay = self.char_delay();
+            if self.budget < delay {
+                break;
+            }
+            self.budget -= delay;
+            self.step(&mut events);
+        }
+        if !self.text_done() {
+            return events;
+        }
+        if !self.end {
+            self.end = true;
+            self.budget = 0;
+            events.push(RunnerEvent::LineCompleted);
+        }
+        if self.ffward && self.can_skip {
+            if self.budget >= FFWARD_END {
+                events.push(self.send_advance(AdvanceCause::FastForward));
+            }
+        } else if self.ffward {
+            self.ffward = false;
+        } else if self.auto && self.budget >= self.auto_speed {
+            events.push(self.send_advance(AdvanceCause::Auto));
+        }
+        events
+    }
+    /// miliseconds until tick would change something, None when nothing happens until
+    /// a call other than tick, e.g. at the end of a line without auto
+    /// # Example
+    /// ```
+    /// async fn run(mut runner: StoryRunner) {
+    ///     while let Some(due) = runner.next_due() {
+    ///         wait(due).await;
+    ///         runner.tick(due);
+    ///     }
+    /// }
+    /// ```
+    pub fn next_due(&self) -> Option<u32> {
+        if self.waiting {
+            return None;
+        }
+        if self.skipping {
+            return Some(0);
+        }
+        let hold = (self.holding && !self.ffward)
+            .then(|| (FFWARD_HOLD + 1).saturating_sub(self.hold_time));
+        let progress = if !self.text_done() {
+            Some(self.char_delay().saturating_sub(self.budget))
+        } else if !self.end {
+            Some(0)
+        } else if self.ffward && self.can_skip {
+            Some(FFWARD_END.saturating_sub(self.budget))
+        } else if self.ffward {
+            Some(0)
+        } else if self.auto {
+            Some(self.auto_speed.saturating_sub(self.budget))
+        } else {
+            None
+        };
+        match (hold, progress) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+    /// a click: the next line at the end, otherwise show the whole line when skipping is allowed
+    pub fn advance(&mut self) -> Option<RunnerEvent> {
+        if self.waiting {
+            None
+        } else if self.end {
+            Some(self.send_advance(AdvanceCause::Click))
+        } else if self.can_skip {
+            self.text_index = self.text.len();
+            self.msg_index = 0;
+            None
+        } else {
+            None
+        }
+    }
+    pub fn toggle_auto(&mut self) -> bool {
+        self.set_auto(!self.auto);
+        self.auto
+    }
+    /// pass the lines before skip_len and the skippable lines
+    pub fn skip(&mut self) {
+        self.skipping = true;
+    }
+    /// the mouse or a finger is pressed on the box, fast-forward starts after FFWARD_HOLD
+    pub fn hold(&mut self, holding: bool) {
+        self.holding = holding;
+        self.hold_time = 0;
+        if !holding {
+            self.ffward = false;
+        }
+    }
+
+    pub fn text(&self) -> &[TextPrint] {
+        &self.text
+    }
+    pub fn story_index(&self) -> usize {
+        self.story_index
+    }
+    /// the part being typed, parts before it are fully shown
+    pub fn text_index(&self) -> usize {
+        self.text_index
+    }
+    /// the characters shown of the part being typed
+    pub fn msg_index(&self) -> usize {
+        self.msg_index
+    }
+    pub fn visible_chars(&self) -> usize {
+        self.text
+            .iter()
+            .take(self.text_index)
+            .map(|t| t.msg.chars().count())
+            .sum::<usize>()
+            + self.msg_index
+    }
+    pub fn is_end(&self) -> bool {
+        self.end
+    }
+    pub fn is_auto(&self) -> bool {
+        self.auto
+    }
+    pub fn is_ffward(&self) -> bool {
+        self.ffward
+    }
+    pub fn is_skipping(&self) -> bool {
+        self.skipping
+    }
+    /// an advance was sent and set_line wasn't called yet
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    fn text_done(&self) -> bool {
+        self.text_index >= self.text.len()
+    }
+    fn char_delay(&self) -> u32 {
+        if self.ffward && self.can_skip {
+            FFWARD_SPEED
+        } else {
+            match self.text.get(self.text_index) {
+                Some(text) => (text.speed as f32 / self.speed) as u32,
+                None => 0,
+            }
+        }
+    }
+    /// one character, or the move to the next part after the last character
+    fn step(&mut self, events: &mut Vec<RunnerEvent>) {
+        let len = self.text[self.text_index].msg.chars().count();
+        if len > self.msg_index {
+            self.msg_index += 1;
+            events.push(RunnerEvent::CharRevealed {
+                part: self.text_index,
+            });
+        } else {
+            self.msg_index = 0;
+            self.text_index += 1;
+        }
+    }
+    fn send_advance(&mut self, cause: AdvanceCause) -> RunnerEvent {
+        self.waiting = true;
+        self.budget = 0;
+        RunnerEvent::Advance(cause)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AdvanceCause, RunnerEvent, StoryRunner, TickTimer, FFWARD_HOLD};
+    use crate::text_print::TextPrint;
+
+    fn line(msg: &str) -> Vec<TextPrint> {
+        vec![TextPrint::default().msg(msg).speed(10)]
+    }
+
+    #[test]
+    fn runner_typewriter() {
+        let mut runner = StoryRunner::new(line("abc"), 0);
+        assert_eq!(
+            runner.tick(25),
+            vec![
+                RunnerEvent::CharRevealed { part: 0 },
+                RunnerEvent::CharRevealed { part: 0 }
+            ]
+        );
+        assert_eq!(runner.visible_chars(), 2);
+        assert_eq!(runner.advance(), None);
+        assert_eq!(runner.visible_chars(), 3);
+        assert_eq!(runner.tick(1), vec![RunnerEvent::LineCompleted]);
+        assert_eq!(
+            runner.advance(),
+            Some(RunnerEvent::Advance(AdvanceCause::Click))
+        );
+        assert!(runner.tick(100).is_empty());
+
+        runner.set_line(line("d"), 1);
+        assert_eq!(runner.story_index(), 1);
+        assert_eq!(runner.visible_chars(), 0);
+    }
+
+    #[test]
+    fn runner_auto_and_ffward() {
+        let mut runner = StoryRunner::new(line("a"), 0).speed(1., 100);
+        assert!(runner.toggle_auto());
+        runner.tick(20);
+        assert!(runner.is_end());
+        assert!(runner.tick(99).is_empty());
+        assert_eq!(
+            runner.tick(1),
+            vec![RunnerEvent::Advance(AdvanceCause::Auto)]
+        );
+
+        runner.set_line(line("abcdef"), 1);
+        runner.set_auto(false);
+        runner.hold(true);
+        runner.tick(FFWARD_HOLD + 1);
+        assert!(runner.is_ffward());
+        assert!(runner
+            .tick(100)
+            .contains(&RunnerEvent::Advance(AdvanceCause::FastForward)));
+        runner.hold(false);
+        assert!(!runner.is_ffward());
+    }
+
+    #[test]
+    fn runner_idle_then_hold() {
+        let mut runner = StoryRunner::new(line("a"), 0);
+        let mut timer = TickTimer::default();
+        runner.tick(timer.elapsed(0));
+        runner.tick(timer.elapsed(20));
+        assert!(runner.is_end());
+        assert_eq!(runner.next_due(), None);
+        timer.stop();
+
+        // pressed after a long pause: the pause is not part of the hold
+        runner.hold(true);
+        assert!(runner.tick(timer.elapsed(5000)).is_empty());
+        assert!(!runner.is_ffward());
+        runner.tick(timer.elapsed(5100));
+        assert!(!runner.is_ffward());
+        runner.hold(false);
+        assert_eq!(
+            runner.advance(),
+            Some(RunnerEvent::Advance(AdvanceCause::Click))
+        );
+    }
+
+    #[test]
+    fn runner_next_due() {
+        let mut runner = StoryRunner::new(line("ab"), 0).speed(2., 100);
+        assert_eq!(runner.next_due(), Some(5));
+        runner.tick(7);
+        assert_eq!(runner.next_due(), Some(3));
+        runner.tick(10);
+        assert!(runner.is_end());
+        assert_eq!(runner.next_due(), None);
+        runner.set_auto(true);
+        assert_eq!(runner.next_due(), Some(100));
+        runner.set_auto(false);
+        runner.hold(true);
+        assert_eq!(runner.next_due(), Some(FFWARD_HOLD + 1));
+    }
+
+    #[test]
+    fn runner_skip() {
+        let mut runner = StoryRunner::new(line("a"), 0).skip_len(2);
+        runner.skip();
+        assert_eq!(
+            runner.tick(1),
+            vec![RunnerEvent::Advance(AdvanceCause::Skip)]
+        );
+        runner.set_line(line("b"), 1);
+        assert_eq!(
+            runner.tick(1),
+            vec![RunnerEvent::Advance(AdvanceCause::Skip)]
+        );
+        runner.set_line(line("c"), 2);
+        runner.tick(1);
+        assert!(!runner.is_skipping());
+        assert_eq!(runner.visible_chars(), 0);
+    }
+}
@@ -0,0 +1,763 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{Debug, Display},
+    rc::Rc,
+};
+
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::input::KeyBindings;
+use crate::message_log::SavedLogEntry;
+use crate::scene::{SceneStack, SCENES};
+use crate::sound_effect::{SoundEffect, CURRENT_MUSIC};
+use crate::story_base::{
+    wait, Story, StoryPosition, CURRENT_STORY, LOG, READ_LINES, STORY_POSITION, TEXTCONFIG,
+};
+use crate::text_print::{FontWeight, TextOption, TextPrint};
+use crate::variable::{Variable, VARIABLES};
+
+/// version written by SaveData::to_json, saves without a version are version 1
+pub const SAVE_FORMAT_VERSION: u32 = 3;
+
+/// a position of an old save mapped by the game to the current storys, see SaveSettings::migrate
+pub type PositionMigration = dyn Fn(&StoryPosition) -> Option<StoryPosition>;
+
+/// why save data can't be loaded
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SaveError {
+    /// the save was written by a newer version of the game
+    UnsupportedVersion(u32),
+    /// the saved line is not in the chapter anymore and the migration didn't map it
+    LineNotFound {
+        chapter: String,
+        index: usize,
+        line_id: String,
+    },
+    /// the storage or the json failed, with the message of the error
+    Unreadable(String),
+}
+impl Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::UnsupportedVersion(version) => write!(
+                f,
+                "save format {version} is newer than {SAVE_FORMAT_VERSION}"
+            ),
+            SaveError::LineNotFound {
+                chapter,
+                index,
+                line_id,
+            } => write!(
+                f,
+                "line {line_id:?} (index {index}) is not in chapter {chapter:?}"
+            ),
+            SaveError::Unreadable(e) => write!(f, "the save can't be read: {e}"),
+        }
+    }
+}
+impl Error for SaveError {}
+
+/// TextPrint without the style and sound closures
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SavedText {
+    pub msg: String,
+    pub color: String,
+    pub option: TextOption,
+    pub size: f32,
+    pub speed: u32,
+    pub is_split: bool,
+    pub font: String,
+    pub font_weight: FontWeight,
+    pub class: String,
+}
+impl From<&TextPrint> for SavedText {
+    fn from(text: &TextPrint) -> Self {
+        SavedText {
+            msg: text.msg.clone(),
+            color: text.color.clone(),
+            option: text.option,
+            size: text.size,
+            speed: text.speed,
+            is_split: text.is_split,
+            font: text.font.clone(),
+            font_weight: text.font_weight,
+            class: text.class.clone(),
+        }
+    }
+}
+impl SavedText {
+    pub fn to_text_print(&self) -> TextPrint {
+        TextPrint::default()
+            .msg(&self.msg)
+            .color(&self.color)
+            .option(self.option)
+            .size(self.size)
+            .speed(self.speed)
+            .is_split(self.is_split)
+            .font(&self.font)
+            .font_weight(self.font_weight)
+            .class(&self.class)
+    }
+}
+
+/// the user settings of TextConfig, kept in a record of their own, see save_settings
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SavedConfig {
+    pub sound_volum: f64,
+    pub music_volum: f64,
+    pub speed: f32,
+    pub auto_speed: u32,
+    pub reduced_motion: bool,
+    #[serde(default)]
+    pub skip_unread: bool,
+    #[serde(default)]
+    pub key_bindings: KeyBindings,
+}
+impl SavedConfig {
+    pub fn capture() -> Self {
+        let config = TEXTCONFIG.read();
+        SavedConfig {
+            sound_volum: config.sound_volum,
+            music_volum: config.music_volum,
+            speed: config.speed,
+            auto_speed: config.auto_speed,
+            reduced_motion: config.reduced_motion,
+            skip_unread: config.skip_unread,
+            key_bindings: config.key_bindings.clone(),
+        }
+    }
+    /// put the settings into TEXTCONFIG
+    pub fn apply(&self) {
+        let mut config = TEXTCONFIG.write();
+        config.sound_volum = self.sound_volum;
+        config.music_volum = self.music_volum;
+        config.speed = self.speed;
+        config.auto_speed = self.auto_speed;
+        config.reduced_motion = self.reduced_motion;
+        config.skip_unread = self.skip_unread;
+        config.key_bindings = self.key_bindings.clone();
+    }
+}
+
+/// # SaveData
+/// A snapshot of the story state. Load it with restore, then show StoryPage
+/// for `position.chapter` with the index from resolve as `skip`.
+/// ## Example
+/// ```
+/// fn test() -> Result<(), Box<dyn Error>> {
+///     let storage = DefaultStorage::default();
+///     SaveData::capture().save(&storage, 1)?;
+///     if let Some(data) = SaveData::load(&storage, 1)? {
+///         data.restore();
+///         // route to data.position.chapter, skip to data.resolve(&storys, None)?
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SaveData {
+    pub version: u32,
+    pub position: StoryPosition,
+    pub variables: BTreeMap<String, Variable>,
+    pub log: Vec<SavedLogEntry>,
+    /// name in SOUND_EFFECTS of the playing music
+    pub music: Option<String>,
+    /// the settings when it was saved, restore leaves the current ones
+    pub config: SavedConfig,
+    /// the scenes of SceneView, empty when it is not used
+    #[serde(default)]
+    pub scenes: SceneStack,
+    /// unix time, seconds
+    pub timestamp: u64,
+    /// speaker and first line of the story, for the slot list
+    pub preview: String,
+}
+impl SaveData {
+    /// snapshot of the story on screen
+    pub fn capture() -> Self {
+        SaveData {
+            version: SAVE_FORMAT_VERSION,
+            position: STORY_POSITION(),
+            variables: VARIABLES(),
+            log: LOG.read().export(),
+            music: CURRENT_MUSIC(),
+            config: SavedConfig::capture(),
+            scenes: SCENES(),
+            timestamp: now(),
+            preview: CURRENT_STORY
+                .read()
+                .as_ref()
+                .map_or_else(String::new, |s| s.preview()),
+        }
+    }
+    /// put the snapshot back into the global state, SCENES too when it was saved, and resume its music.
+    /// the settings are the player's, they are not changed
+    pub fn restore(&self) {
+        *STORY_POSITION.write() = self.position.clone();
+        *VARIABLES.write() = self.variables.clone();
+        LOG.write().import(&self.log);
+        if !self.scenes.is_empty() {
+            *SCENES.write() = self.scenes.clone();
+        }
+        match &self.music {
+            Some(music) => {
+                if let Err(e) = SoundEffect::play_music(music) {
+                    tracing::warn!("can't resume music {music}: {e}");
+                }
+            }
+            None => SoundEffect::music_stop(),
+        }
+    }
+
+    /// index of the saved line in storys, an old position goes through migrate when its line is gone
+    /// # Example
+    /// ```
+    /// fn test(data: SaveData, storys: Vec<Story>) {
+    ///     let migrate = |old: &StoryPosition| {
+    ///         // line 10 of the first release moved to 12
+    ///         (old.index == 10).then(|| StoryPosition { index: 12, ..old.clone() })
+    ///     };
+    ///     match data.resolve(&storys, Some(&migrate)) {
+    ///         Ok(index) => { /* StoryPage with skip: index */ }
+    ///         Err(e) => { /* tell the player the save can't be used */ }
+    ///     }
+    /// }
+    /// ```
+    pub fn resolve(
+        &self,
+        storys: &[Story],
+        migrate: Option<&PositionMigration>,
+    ) -> Result<usize, SaveError> {
+        let found = if self.position.line_id.is_empty() {
+            // version 1 saves only know the index, it can't be checked
+            None
+        } else {
+            self.position.find(storys)
+        };
+        found
+            .or_else(|| {
+                migrate
+                    .and_then(|m| m(&self.position))
+                    .and_then(|p| p.find(storys))
+            })
+            .ok_or_else(|| SaveError::LineNotFound {
+                chapter: self.position.chapter.clone(),
+                index: self.position.index,
+                line_id: self.position.line_id.clone(),
+            })
+    }
+
+    pub fn to_json(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string(self)?)
+    }
+    /// read json of this or an older save format
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn Error>> {
+        let mut value: serde_json::Value = serde_json::from_str(json)?;
+        let version = value
+            .get("version")
+            .and_then(|v| v.as_u64())
+            .map_or(1, |v| v as u32);
+        if version > SAVE_FORMAT_VERSION {
+            return Err(SaveError::UnsupportedVersion(version).into());
+        }
+        if version < 3 {
+            // 2 added the version and StoryPosition::line_id, which has a serde default.
+            // 3 changed log entries from a list of texts to SavedLogEntry
+            if let Some(log) = value.get_mut("log").and_then(|l| l.as_array_mut()) {
+                for entry in log.iter_mut().filter(|e| e.is_array()) {
+                    *entry = serde_json::json!({ "msg": entry.take() });
+                }
+            }
+            value["version"] = SAVE_FORMAT_VERSION.into();
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// write the snapshot to the numbered slot
+    pub fn save(
+        &self,
+        storage: &(impl SaveStorage + ?Sized),
+        slot: u32,
+    ) -> Result<(), Box<dyn Error>> {
+        storage.write(&slot_key(slot), &self.to_json()?)
+    }
+    /// read the numbered slot, None when it is empty
+    pub fn load(
+        storage: &(impl SaveStorage + ?Sized),
+        slot: u32,
+    ) -> Result<Option<Self>, Box<dyn Error>> {
+        match storage.read(&slot_key(slot))? {
+            Some(json) => Ok(Some(SaveData::from_json(&json)?)),
+            None => Ok(None),
+        }
+    }
+    /// empty the numbered slot
+    pub fn delete(storage: &(impl SaveStorage + ?Sized), slot: u32) -> Result<(), Box<dyn Error>> {
+        storage.remove(&slot_key(slot))
+    }
+    /// the used slots in the range, for a save/load screen
+    pub fn list(
+        storage: &(impl SaveStorage + ?Sized),
+        slots: std::ops::Range<u32>,
+    ) -> Result<Vec<(u32, SaveData)>, Box<dyn Error>> {
+        let mut result = vec![];
+        for slot in slots {
+            if let Some(data) = SaveData::load(storage, slot)? {
+                result.push((slot, data));
+            }
+        }
+        Ok(result)
+    }
+}
+
+const READ_LINES_KEY: &str = "read-lines";
+const SETTINGS_KEY: &str = "settings";
+/// StoryPage writes READ_LINES and the settings when they haven't changed for this long, miliseconds
+pub(crate) const READ_LINES_DELAY: u32 = 1000;
+
+/// write the settings of TEXTCONFIG, they don't belong to a save slot
+pub fn save_settings(storage: &(impl SaveStorage + ?Sized)) -> Result<(), Box<dyn Error>> {
+    storage.write(
+        SETTINGS_KEY,
+        &serde_json::to_string(&SavedConfig::capture())?,
+    )
+}
+/// put the saved settings into TEXTCONFIG, false when none were saved
+pub fn load_settings(storage: &(impl SaveStorage + ?Sized)) -> Result<bool, Box<dyn Error>> {
+    let Some(json) = storage.read(SETTINGS_KEY)? else {
+        return Ok(false);
+    };
+    serde_json::from_str::<SavedConfig>(&json)?.apply();
+    Ok(true)
+}
+
+/// write READ_LINES, they are shared by every save slot
+pub fn save_read_lines(storage: &(impl SaveStorage + ?Sized)) -> Result<(), Box<dyn Error>> {
+    let mut lines: Vec<String> = READ_LINES.read().iter().cloned().collect();
+    lines.sort();
+    storage.write(READ_LINES_KEY, &serde_json::to_string(&lines)?)
+}
+/// add the saved read lines to READ_LINES
+pub fn load_read_lines(storage: &(impl SaveStorage + ?Sized)) -> Result<(), Box<dyn Error>> {
+    if let Some(json) = storage.read(READ_LINES_KEY)? {
+        let lines: Vec<String> = serde_json::from_str(&json)?;
+        READ_LINES.write().extend(lines);
+    }
+    Ok(())
+}
+
+/// slot used by the Q.Save and Q.Load buttons
+pub const QUICK_SLOT: u32 = u32::MAX;
+/// slot written by the auto-save
+pub const AUTO_SLOT: u32 = u32::MAX - 1;
+
+fn slot_key(slot: u32) -> String {
+    match slot {
+        QUICK_SLOT => "quick".to_owned(),
+        AUTO_SLOT => "auto".to_owned(),
+        slot => format!("slot-{slot}"),
+    }
+}
+
+/// # SaveSettings
+/// Quick-save, quick-load and auto-save of StoryPage.
+/// The quick save and quick load keys of KeyBindings (F5 and F9) work when shortcuts are on.
+/// The settings of TEXTCONFIG and READ_LINES are stored apart from the slots, loading a slot keeps them.
+/// ## Example
+/// ```
+/// #[component]
+/// pub fn TestStoryPage() -> Element {
+///     rsx! {
+///         StoryPage{
+///             storys: vec![/*some story*/],
+///             next: rsx!{},
+///             on_next: move |_| {},
+///             skip_len: 0,
+///             skip: 0,
+///             other_setting: rsx!{},
+///             chapter: "prologue",
+///             save: SaveSettings::default().autosave_every(20),
+///             on_load: move |data: SaveData| {
+///                 // a save of another chapter was loaded, it is not restored yet.
+///                 // route to data.position.chapter, resolve with its storys, then restore
+///             },
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SaveSettings {
+    pub storage: Rc<dyn SaveStorage>,
+    /// auto-save after this many advances, 0 turns it off
+    pub autosave_every: u32,
+    /// auto-save when a story marked as choice point is shown
+    pub autosave_choice: bool,
+    /// InputAction::QuickSave and QuickLoad keys
+    pub shortcuts: bool,
+    /// maps positions of old saves whose line is gone, see SaveData::resolve
+    pub migrate: Option<Rc<PositionMigration>>,
+}
+impl PartialEq for SaveSettings {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.storage, &other.storage)
+            && self.autosave_every == other.autosave_every
+            && self.autosave_choice == other.autosave_choice
+            && self.shortcuts == other.shortcuts
+            && match (&self.migrate, &other.migrate) {
+                (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+                (None, None) => true,
+                _ => false,
+            }
+    }
+}
+impl Default for SaveSettings {
+    fn default() -> Self {
+        SaveSettings {
+            storage: Rc::new(DefaultStorage::default()),
+            autosave_every: 10,
+            autosave_choice: true,
+            shortcuts: true,
+            migrate: None,
+        }
+    }
+}
+impl SaveSettings {
+    pub fn storage(mut self, storage: Rc<dyn SaveStorage>) -> Self {
+        self.storage = storage;
+        self
+    }
+    pub fn autosave_every(mut self, autosave_every: u32) -> Self {
+        self.autosave_every = autosave_every;
+        self
+    }
+    pub fn autosave_choice(mut self, autosave_choice: bool) -> Self {
+        self.autosave_choice = autosave_choice;
+        self
+    }
+    pub fn shortcuts(mut self, shortcuts: bool) -> Self {
+        self.shortcuts = shortcuts;
+        self
+    }
+    pub fn migrate(mut self, migrate: Rc<PositionMigration>) -> Self {
+        self.migrate = Some(migrate);
+        self
+    }
+
+    /// capture now and write in a task, so the key handler returns first.
+    /// the write still runs on the UI thread, a slow storage holds it up while writing.
+    /// the read lines are written too
+    pub fn save_in_background(&self, slot: u32) {
+        let data = SaveData::capture();
+        let storage = Rc::clone(&self.storage);
+        spawn(async move {
+            wait(0).await;
+            if let Err(e) = data.save(storage.as_ref(), slot) {
+                tracing::warn!("save to slot {slot} failed: {e}");
+            }
+            if let Err(e) = save_read_lines(storage.as_ref()) {
+                tracing::warn!("saving read lines failed: {e}");
+            }
+        });
+    }
+    /// write READ_LINES now, StoryPage does this a second after they change and when it closes
+    pub fn save_read_lines(&self) {
+        if let Err(e) = save_read_lines(self.storage.as_ref()) {
+            tracing::warn!("saving read lines failed: {e}");
+        }
+    }
+    /// add the read lines of the last sessions to READ_LINES, StoryPage does this on start
+    pub fn load_read_lines(&self) {
+        if let Err(e) = load_read_lines(self.storage.as_ref()) {
+            tracing::warn!("loading read lines failed: {e}");
+        }
+    }
+    /// write the settings now, StoryPage does this a second after they change and when it closes
+    pub fn save_settings(&self) {
+        if let Err(e) = save_settings(self.storage.as_ref()) {
+            tracing::warn!("saving settings failed: {e}");
+        }
+    }
+    /// put the settings of the last session into TEXTCONFIG, StoryPage does this on start
+    pub fn load_settings(&self) {
+        if let Err(e) = load_settings(self.storage.as_ref()) {
+            tracing::warn!("loading settings failed: {e}");
+        }
+    }
+    pub fn quick_save(&self) {
+        self.save_in_background(QUICK_SLOT);
+    }
+    pub fn auto_save(&self) {
+        self.save_in_background(AUTO_SLOT);
+    }
+    /// read the quick-save, None when there is none.
+    /// it is not restored, resolve it against the storys first
+    pub fn quick_load(&self) -> Result<Option<SaveData>, SaveError> {
+        SaveData::load(self.storage.as_ref(), QUICK_SLOT).map_err(|e| {
+            match e.downcast::<SaveError>() {
+                Ok(e) => *e,
+                Err(e) => SaveError::Unreadable(e.to_string()),
+            }
+        })
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn now() -> u64 {
+    (js_sys::Date::now() / 1000.) as u64
+}
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// where save data is kept
+pub trait SaveStorage {
+    fn write(&self, key: &str, data: &str) -> Result<(), Box<dyn Error>>;
+    fn read(&self, key: &str) -> Result<Option<String>, Box<dyn Error>>;
+    fn remove(&self, key: &str) -> Result<(), Box<dyn Error>>;
+}
+
+/// saves as json files in a directory
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, PartialEq, Debug)]
+pub struct FileStorage {
+    pub dir: std::path::PathBuf,
+}
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for FileStorage {
+    fn default() -> Self {
+        FileStorage::new("saves")
+    }
+}
+#[cfg(not(target_arch = "wasm32"))]
+impl FileStorage {
+    pub fn new(dir: impl Into<std::path::PathBuf>) -> Self {
+        FileStorage { dir: dir.into() }
+    }
+    fn path(&self, key: &str) -> std::path::PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+}
+#[cfg(not(target_arch = "wasm32"))]
+impl SaveStorage for FileStorage {
+    fn write(&self, key: &str, data: &str) -> Result<(), Box<dyn Error>> {
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.path(key), data)?;
+        Ok(())
+    }
+    fn read(&self, key: &str) -> Result<Option<String>, Box<dyn Error>> {
+        match std::fs::read_to_string(self.path(key)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+    fn remove(&self, key: &str) -> Result<(), Box<dyn Error>> {
+        match std::fs::remove_file(self.path(key)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// saves in the browser localStorage, keys are prefixed
+#[cfg(target_arch = "wasm32")]
+#[derive(Clone, PartialEq, Debug)]
+pub struct LocalStorage {
+    pub prefix: String,
+}
+#[cfg(target_arch = "wasm32")]
+impl Default for LocalStorage {
+    fn default() -> Self {
+        LocalStorage::new("story_lib")
+    }
+}
+#[cfg(target_arch = "wasm32")]
+impl LocalStorage {
+    pub fn new(prefix: &str) -> Self {
+        LocalStorage {
+            prefix: prefix.to_owned(),
+        }
+    }
+    fn storage(&self) -> Result<web_sys::Storage, Box<dyn Error>> {
+        web_sys::window()
+            .ok_or("no window")?
+            .local_storage()
+            .map_err(|e| format!("{e:?}"))?
+            .ok_or_else(|| "localStorage is not available".into())
+    }
+}
+#[cfg(target_arch = "wasm32")]
+impl SaveStorage for LocalStorage {
+    fn write(&self, key: &str, data: &str) -> Result<(), Box<dyn Error>> {
+        self.storage()?
+            .set_item(&format!("{}-{key}", self.prefix), data)
+            .map_err(|e| format!("{e:?}").into())
+    }
+    fn read(&self, key: &str) -> Result<Option<String>, Box<dyn Error>> {
+        self.storage()?
+            .get_item(&format!("{}-{key}", self.prefix))
+            .map_err(|e| format!("{e:?}").into())
+    }
+    fn remove(&self, key: &str) -> Result<(), Box<dyn Error>> {
+        self.storage()?
+            .remove_item(&format!("{}-{key}", self.prefix))
+            .map_err(|e| format!("{e:?}").into())
+    }
+}
+
+/// FileStorage on desktop, LocalStorage on wasm
+#[cfg(not(target_arch = "wasm32"))]
+pub type DefaultStorage = FileStorage;
+/// FileStorage on desktop, LocalStorage on wasm
+#[cfg(target_arch = "wasm32")]
+pub type DefaultStorage = LocalStorage;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{FileStorage, SaveData, SaveError, SavedConfig, SavedText, SAVE_FORMAT_VERSION};
+    use crate::input::{InputAction, KeyBindings};
+    use crate::message_log::SavedLogEntry;
+    use crate::scene::SceneStack;
+    use crate::story_base::{assign_line_ids, derive_line_id, Story, StoryPosition};
+    use crate::text_print::TextPrint;
+    use crate::variable::Variable;
+
+    fn line(msg: &str) -> Story {
+        Story::default().msg(TextPrint::parse(format!("{{{{}}}}{msg}")))
+    }
+
+    fn sample() -> SaveData {
+        SaveData {
+            version: SAVE_FORMAT_VERSION,
+            position: StoryPosition {
+                chapter: "prologue".to_owned(),
+                index: 1,
+                line_id: derive_line_id("prologue", "", "hello"),
+            },
+            variables: BTreeMap::from([("met_alice".to_owned(), Variable::Bool(true))]),
+            log: vec![SavedLogEntry {
+                title: vec![SavedText::from(&TextPrint::default().msg("alice"))],
+                msg: vec![SavedText::from(
+                    &TextPrint::default().msg("안녕?").color("red"),
+                )],
+                line_id: line("hello").line_id(),
+                timestamp: 1_700_000_000,
+                choice: None,
+                voice: Some("alice-hello".to_owned()),
+            }],
+            music: Some("theme".to_owned()),
+            config: SavedConfig {
+                sound_volum: 1.,
+                music_volum: 0.5,
+                speed: 1.,
+                auto_speed: 5000,
+                reduced_motion: false,
+                skip_unread: false,
+                key_bindings: KeyBindings::default().bind("KeyS", InputAction::FastForward),
+            },
+            scenes: {
+                let mut scenes = SceneStack::default();
+                scenes.push("title");
+                scenes.push("prologue");
+                scenes
+            },
+            timestamp: 1_700_000_000,
+            preview: "alice: 안녕?".to_owned(),
+        }
+    }
+
+    #[test]
+    fn save_data_json() {
+        let data = sample();
+        let json = data.to_json().unwrap();
+        assert_eq!(SaveData::from_json(&json).unwrap(), data);
+        assert_eq!(
+            data.log[0].msg[0].to_text_print(),
+            TextPrint::default().msg("안녕?").color("red")
+        );
+    }
+
+    #[test]
+    fn file_storage_slot() {
+        let dir = std::env::temp_dir().join(format!("story_lib_save_{}", std::process::id()));
+        let storage = FileStorage::new(&dir);
+        assert_eq!(SaveData::load(&storage, 1).unwrap(), None);
+
+        sample().save(&storage, 1).unwrap();
+        assert_eq!(SaveData::load(&storage, 1).unwrap(), Some(sample()));
+        assert_eq!(SaveData::list(&storage, 0..5).unwrap().len(), 1);
+
+        SaveData::delete(&storage, 1).unwrap();
+        assert_eq!(SaveData::load(&storage, 1).unwrap(), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn save_data_resolve() {
+        let data = sample();
+        let storys = vec![line("hi"), line("new line"), line("hello")];
+        assert_eq!(data.resolve(&storys, None), Ok(2));
+
+        let storys = vec![line("hi"), line("hello?")];
+        assert_eq!(
+            data.resolve(&storys, None),
+            Err(SaveError::LineNotFound {
+                chapter: "prologue".to_owned(),
+                index: 1,
+                line_id: data.position.line_id.clone(),
+            })
+        );
+        let migrate = |old: &StoryPosition| {
+            Some(StoryPosition {
+                line_id: "".to_owned(),
+                ..old.clone()
+            })
+        };
+        assert_eq!(data.resolve(&storys, Some(&migrate)), Ok(1));
+    }
+
+    #[test]
+    fn save_data_resolve_chapter() {
+        // StoryPage saves the ids of assign_line_ids, the game resolves against its raw storys
+        let storys = vec![line("..."), line("hello"), line("..."), line("hello")];
+        let page = assign_line_ids("prologue", storys.clone()).unwrap();
+        let mut data = sample();
+        data.position = StoryPosition {
+            chapter: "prologue".to_owned(),
+            index: 3,
+            line_id: page[3].line_id(),
+        };
+        assert_eq!(data.resolve(&storys, None), Ok(3));
+        data.position.index = 0;
+        assert_eq!(data.resolve(&storys, None), Ok(3));
+
+        // an id saved without a chapter is still found
+        data.position.line_id = line("hello").line_id();
+        assert_eq!(data.resolve(&storys, None), Ok(1));
+    }
+
+    #[test]
+    fn save_data_old_version() {
+        let mut json: serde_json::Value =
+            serde_json::from_str(&sample().to_json().unwrap()).unwrap();
+        json.as_object_mut().unwrap().remove("version");
+        json["position"].as_object_mut().unwrap().remove("line_id");
+        json["log"] = serde_json::json!([[json["log"][0]["msg"][0].clone()]]);
+        let data = SaveData::from_json(&json.to_string()).unwrap();
+        assert_eq!(data.version, SAVE_FORMAT_VERSION);
+        assert_eq!(data.position.line_id, "");
+        assert_eq!(data.log[0].msg, sample().log[0].msg);
+        assert_eq!(data.log[0].line_id, "");
+        assert!(data.resolve(&[line("hi"), line("hello")], None).is_err());
+
+        json["version"] = (SAVE_FORMAT_VERSION + 1).into();
+        assert!(SaveData::from_json(&json.to_string()).is_err());
+    }
+}
@@ -0,0 +1,410 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::story_base::{wait, TEXTCONFIG};
+
+/// Global variables for the scene stack SceneView shows, the last key is on screen
+pub static SCENES: GlobalSignal<SceneStack> = Signal::global(SceneStack::default);
+
+/// # SceneStack
+/// Keys of the scenes, the last one is on screen. Saved with SaveData,
+/// so it only holds keys of the scenes given to SceneView.
+/// ## Example
+/// ```
+/// fn test() {
+///     let mut stack = SceneStack::default();
+///     stack.push("title");
+///     stack.push("gallery");
+///     assert_eq!(stack.pop().as_deref(), Some("gallery"));
+///     stack.replace("prologue");
+///     assert_eq!(stack.current(), Some("prologue"));
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SceneStack {
+    pub keys: Vec<String>,
+    /// a new number on every push, so a scene replaced by the same key is mounted again
+    #[serde(skip)]
+    pushes: Vec<u64>,
+    #[serde(skip)]
+    next: u64,
+}
+impl PartialEq for SceneStack {
+    fn eq(&self, other: &Self) -> bool {
+        self.keys == other.keys
+    }
+}
+impl Eq for SceneStack {}
+impl SceneStack {
+    pub fn push(&mut self, key: &str) {
+        self.sync();
+        self.keys.push(key.to_owned());
+        self.pushes.push(self.next);
+        self.next += 1;
+    }
+    /// remove the scene on screen and go back to the one under it,
+    /// the last scene is not removed
+    pub fn pop(&mut self) -> Option<String> {
+        if self.keys.len() < 2 {
+            return None;
+        }
+        self.sync();
+        self.pushes.pop();
+        self.keys.pop()
+    }
+    /// change the scene on screen, the scenes under it stay
+    pub fn replace(&mut self, key: &str) {
+        self.sync();
+        self.pushes.pop();
+        self.keys.pop();
+        self.push(key);
+    }
+    /// numbers for keys that were loaded or pushed to keys directly
+    fn sync(&mut self) {
+        self.pushes.truncate(self.keys.len());
+        while self.pushes.len() < self.keys.len() {
+            self.pushes.push(self.pushes.len() as u64);
+        }
+        self.next = self.next.max(self.keys.len() as u64);
+    }
+    pub fn current(&self) -> Option<&str> {
+        self.keys.last().map(|k| k.as_str())
+    }
+    pub fn apply(&mut self, change: SceneChange) {
+        match change {
+            SceneChange::Push(key) => self.push(&key),
+            SceneChange::Pop => {
+                if self.pop().is_none() {
+                    tracing::warn!("can't pop the last scene");
+                }
+            }
+            SceneChange::Replace(key) => self.replace(&key),
+        }
+    }
+    /// an id for each scene, new for every push and replace
+    fn ids(&self) -> Vec<(String, String)> {
+        self.keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let push = self.pushes.get(i).copied().unwrap_or(i as u64);
+                (format!("{push}-{key}"), key.clone())
+            })
+            .collect()
+    }
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+/// a change of SCENES, e.g. StoryPage::next_scene
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SceneChange {
+    Push(String),
+    Pop,
+    Replace(String),
+}
+impl SceneChange {
+    pub fn apply(self) {
+        SCENES.write().apply(self);
+    }
+}
+
+/// show the scene over the current one, pop_scene comes back
+pub fn push_scene(key: &str) {
+    SceneChange::Push(key.to_owned()).apply();
+}
+/// back to the scene under the current one
+pub fn pop_scene() {
+    SceneChange::Pop.apply();
+}
+/// show the scene instead of the current one
+pub fn replace_scene(key: &str) {
+    SceneChange::Replace(key.to_owned()).apply();
+}
+
+/// how SceneView changes to the next scene
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SceneTransition {
+    #[default]
+    None,
+    /// the new scene fades in
+    Fade,
+    FadeThroughBlack,
+}
+
+type RenderScene = Rc<dyn Fn() -> Element>;
+
+/// # Scenes
+/// The scenes SceneView can show, by key. A scene should return a component.
+/// A scene under a pushed one stays mounted but hidden and paused, see use_scene_active,
+/// so it goes on where it was after pop_scene. Its state is dropped when it is popped or replaced.
+/// ## Example
+/// ```
+/// fn test() -> Scenes {
+///     Scenes::default()
+///         .scene("title", || rsx! { TitleScreen{} })
+///         .scene("prologue", || rsx! { Prologue{} })
+///         .transition(SceneTransition::FadeThroughBlack, 800)
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct Scenes {
+    scenes: BTreeMap<String, RenderScene>,
+    pub transition: SceneTransition,
+    /// miliseconds
+    pub duration: u32,
+}
+impl PartialEq for Scenes {
+    fn eq(&self, other: &Self) -> bool {
+        self.transition == other.transition
+            && self.duration == other.duration
+            && self.scenes.len() == other.scenes.len()
+            && self
+                .scenes
+                .iter()
+                .zip(other.scenes.iter())
+                .all(|((a, ra), (b, rb))| a == b && Rc::ptr_eq(ra, rb))
+    }
+}
+impl Scenes {
+    pub fn scene(mut self, key: &str, render: impl Fn() -> Element + 'static) -> Self {
+        self.scenes.insert(key.to_owned(), Rc::new(render));
+        self
+    }
+    pub fn transition(mut self, transition: SceneTransition, duration: u32) -> Self {
+        self.transition = transition;
+        self.duration = duration;
+        self
+    }
+    pub fn contains(&self, key: &str) -> bool {
+        self.scenes.contains_key(key)
+    }
+    fn render(&self, key: &str) -> Element {
+        match self.scenes.get(key) {
+            Some(render) => render(),
+            None => {
+                tracing::warn!("no scene {key}");
+                None
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Stage {
+    Idle,
+    /// the first frame of a transition, the starting style is drawn
+    Start,
+    /// fading in the new scene, or the black cover
+    Run,
+    /// fade through black only, the new scene is under the fading cover
+    Reveal,
+}
+
+fn scene_style(transition: SceneTransition, stage: Stage, duration: u32) -> String {
+    match (transition, stage) {
+        (SceneTransition::Fade, Stage::Start) => "opacity: 0;".to_owned(),
+        (SceneTransition::Fade, Stage::Run) => {
+            format!("transition: opacity {duration}ms;opacity: 1;")
+        }
+        _ => "".to_owned(),
+    }
+}
+
+fn cover_style(stage: Stage, duration: u32) -> String {
+    let opacity = if stage == Stage::Run { 1 } else { 0 };
+    format!(
+        "position: fixed;inset: 0;background-color: black;z-index: 100;transition: opacity {}ms;opacity: {opacity};",
+        duration / 2
+    )
+}
+
+/// the scene of a SceneView is shown, or a component is outside of one
+#[derive(Clone, Copy)]
+struct SceneActive(Signal<bool>);
+
+/// false while SceneView keeps the scene hidden under a pushed one.
+/// StoryPage stops its typewriter, auto-save and screen effects then
+pub fn use_scene_active() -> ReadOnlySignal<bool> {
+    use_hook(|| {
+        let active = try_consume_context::<SceneActive>()
+            .map_or_else(|| Signal::new(true), |active| active.0);
+        ReadOnlySignal::new(active)
+    })
+}
+
+#[component]
+fn SceneFrame(scenes: Scenes, scene: String, active: bool, style: String) -> Element {
+    let mut shown = use_context_provider(|| SceneActive(Signal::new(active))).0;
+    use_effect(use_reactive(&active, move |active| {
+        if *shown.peek() != active {
+            shown.set(active);
+        }
+    }));
+    rsx! {
+        div{
+            class: "scene",
+            style: "{style}",
+            {scenes.render(&scene)}
+        }
+    }
+}
+
+/// # SceneView
+/// Shows the scene on top of SCENES, the scene manager that needs no url routing.
+/// The scenes under it are kept hidden and paused. initial is pushed when SCENES is empty.
+/// Change scenes with push_scene, pop_scene, replace_scene or StoryPage::next_scene.
+/// ## Example
+/// ```
+/// #[component]
+/// fn App() -> Element {
+///     rsx! {
+///         SceneView{
+///             scenes: Scenes::default()
+///                 .scene("title", || rsx! { TitleScreen{} })
+///                 .scene("prologue", || rsx! { Prologue{} }),
+///             initial: "title",
+///         }
+///     }
+/// }
+/// ```
+#[component]
+pub fn SceneView(scenes: Scenes, initial: String) -> Element {
+    use_effect(move || {
+        if SCENES.peek().is_empty() {
+            push_scene(&initial);
+        }
+    });
+    // (id, key) of the scene on top
+    let top = use_memo(move || SCENES.read().ids().pop());
+    let mut shown = use_signal(|| top.peek().clone());
+    let mut stage = use_signal(|| Stage::Idle);
+    let mut transition_count = use_signal(|| 0_usize);
+    let (transition, duration) = (scenes.transition, scenes.duration);
+
+    use_effect(use_reactive(
+        (&transition, &duration),
+        move |(transition, duration)| {
+            let top = top();
+            if *shown.peek() == top {
+                return;
+            }
+            // the first scene has nothing to change from
+            if transition == SceneTransition::None
+                || shown.peek().is_none()
+                || duration == 0
+                || TEXTCONFIG.peek().reduced_motion
+            {
+                shown.set(top);
+                *stage.write() = Stage::Idle;
+                return;
+            }
+            *transition_count.write() += 1;
+            let count = *transition_count.peek();
+            if transition == SceneTransition::Fade {
+                shown.set(top.clone());
+            }
+            *stage.write() = Stage::Start;
+            spawn(async move {
+                // one frame with the starting style so the browser has a start point
+                wait(16).await;
+                if *transition_count.peek() != count {
+                    return;
+                }
+                *stage.write() = Stage::Run;
+                if transition == SceneTransition::FadeThroughBlack {
+                    wait(duration / 2).await;
+                    if *transition_count.peek() != count {
+                        return;
+                    }
+                    shown.set(top);
+                    *stage.write() = Stage::Reveal;
+                    wait(duration / 2).await;
+                } else {
+                    wait(duration).await;
+                }
+                if *transition_count.peek() == count {
+                    *stage.write() = Stage::Idle;
+                }
+            });
+        },
+    ));
+
+    // the scenes in SCENES, and the one leaving until the transition has hidden it
+    let mut mounted = SCENES.read().ids();
+    if let Some(leaving) = shown() {
+        if !mounted.contains(&leaving) {
+            mounted.push(leaving);
+        }
+    }
+    let shown_id = shown().map(|(id, _)| id);
+
+    rsx! {
+        // keyed, so a new scene is mounted from the start and the ones under it keep their state
+        for (id, key) in mounted {
+            SceneFrame{
+                key: "{id}",
+                scenes: scenes.clone(),
+                scene: key,
+                active: shown_id.as_ref() == Some(&id),
+                style: if shown_id.as_ref() == Some(&id) { scene_style(transition, stage(), duration) } else { "display: none;".to_owned() },
+            }
+        }
+        if transition == SceneTransition::FadeThroughBlack && stage() != Stage::Idle {
+            div{
+                class: "scene-cover",
+                style: "{cover_style(stage(), duration)}",
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{scene_style, SceneChange, SceneStack, SceneTransition, Stage};
+
+    #[test]
+    fn scene_stack() {
+        let mut stack = SceneStack::default();
+        stack.apply(SceneChange::Push("title".to_owned()));
+        stack.apply(SceneChange::Pop);
+        assert_eq!(stack.current(), Some("title"));
+
+        stack.apply(SceneChange::Push("prologue".to_owned()));
+        stack.apply(SceneChange::Push("gallery".to_owned()));
+        stack.apply(SceneChange::Replace("settings".to_owned()));
+        assert_eq!(stack.keys, vec!["title", "prologue", "settings"]);
+        stack.apply(SceneChange::Pop);
+        assert_eq!(stack.current(), Some("prologue"));
+        stack.push("prologue");
+        let ids = stack.ids();
+        assert_eq!(ids[1].1, ids[2].1);
+        assert_ne!(ids[1].0, ids[2].0);
+        // the same key replaced is another scene
+        stack.replace("prologue");
+        assert_ne!(stack.ids()[2].0, ids[2].0);
+        assert_eq!(stack.ids()[..2], ids[..2]);
+
+        let json = serde_json::to_string(&stack).unwrap();
+        assert_eq!(serde_json::from_str::<SceneStack>(&json).unwrap(), stack);
+    }
+
+    #[test]
+    fn scene_fade_style() {
+        assert_eq!(
+            scene_style(SceneTransition::Fade, Stage::Run, 300),
+            "transition: opacity 300ms;opacity: 1;"
+        );
+        assert_eq!(
+            scene_style(SceneTransition::FadeThroughBlack, Stage::Run, 300),
+            ""
+        );
+    }
+}
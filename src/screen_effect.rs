@@ -0,0 +1,336 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+use rand::{thread_rng, Rng};
+
+use crate::scene::use_scene_active;
+use crate::story_base::{wait, TEXTCONFIG};
+
+/// Global variables for screen effects waiting to be played by StoryPage
+pub static SCREEN_EFFECTS: GlobalSignal<Vec<ScreenEffect>> = Signal::global(Vec::new);
+
+thread_local! {
+    /// how many use_screen_effects are mounted in a shown scene, nothing is queued without one
+    static PLAYERS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// # ScreenEffect
+/// A camera effect on the StoryPage scene.
+/// Shake, flash and fade are skipped when TextConfig::reduced_motion is set.
+/// ## Example
+/// ```
+/// fn test() {
+///     let story = Story::default()
+///         .add_effect(ScreenEffect::shake(8.))
+///         .add_effect(ScreenEffect::tint("rgba(160, 120, 60, 0.3)"));
+///     assert_eq!(story.effects.len(), 2);
+/// }
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub enum ScreenEffect {
+    /// intensity in px, miliseconds
+    Shake { intensity: f32, duration: u32 },
+    /// the color covers the scene and fades away, miliseconds
+    Flash { color: String, duration: u32 },
+    /// fade to the color and back, miliseconds
+    Fade { color: String, duration: u32 },
+    /// color laid over the scene until cleared, use a translucent color
+    Tint(String),
+    /// darkened corners until cleared, strength 0 ~ 1
+    Vignette(f32),
+    /// remove tint and vignette
+    Clear,
+}
+impl ScreenEffect {
+    pub fn shake(intensity: f32) -> Self {
+        ScreenEffect::Shake {
+            intensity,
+            duration: 500,
+        }
+    }
+    pub fn flash(color: &str) -> Self {
+        ScreenEffect::Flash {
+            color: color.to_owned(),
+            duration: 400,
+        }
+    }
+    pub fn fade() -> Self {
+        ScreenEffect::Fade {
+            color: "black".to_owned(),
+            duration: 1000,
+        }
+    }
+    pub fn tint(color: &str) -> Self {
+        ScreenEffect::Tint(color.to_owned())
+    }
+    pub fn vignette(strength: f32) -> Self {
+        ScreenEffect::Vignette(strength)
+    }
+    /// play the effect on the StoryPage on screen.
+    /// it is dropped when no StoryPage is mounted, e.g. from a LightMessageBox alone,
+    /// so it isn't played later by the next one
+    pub fn trigger(self) {
+        if PLAYERS.with(|p| p.get()) == 0 {
+            tracing::debug!("no StoryPage to play {self:?}");
+            return;
+        }
+        SCREEN_EFFECTS.write().push(self);
+    }
+
+    /// parse the markup value, e.g. `shake(8)`, `shake(8, 300)`, `flash(red)`, `fade(black, 2000)`,
+    /// `tint(rgba(0,0,0,0.3))`, `vignette(0.5)` and `clear`
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     assert_eq!(ScreenEffect::parse("flash(red)"), Some(ScreenEffect::flash("red")));
+    /// }
+    /// ```
+    pub fn parse(s: &str) -> Option<ScreenEffect> {
+        let s = s.trim();
+        let (command, value) = match s.split_once('(') {
+            Some((command, value)) => (command.trim(), value.trim().strip_suffix(')')?),
+            None => (s, ""),
+        };
+        // the color may contain commas, the duration is the last number
+        let split_duration = |default: u32| match value.rsplit_once(',') {
+            Some((rest, d)) => match d.trim().parse::<u32>() {
+                Ok(d) => (rest.trim(), d),
+                Err(_) => (value.trim(), default),
+            },
+            None => (value.trim(), default),
+        };
+        match command {
+            "shake" => {
+                let (intensity, duration) = split_duration(500);
+                let intensity: f32 = intensity.parse().ok()?;
+                (intensity.is_finite() && intensity >= 0.).then_some(ScreenEffect::Shake {
+                    intensity,
+                    duration,
+                })
+            }
+            "flash" => {
+                let (color, duration) = split_duration(400);
+                let color = if color.is_empty() { "white" } else { color };
+                Some(ScreenEffect::Flash {
+                    color: color.to_owned(),
+                    duration,
+                })
+            }
+            "fade" => {
+                let (color, duration) = split_duration(1000);
+                let color = if color.is_empty() { "black" } else { color };
+                Some(ScreenEffect::Fade {
+                    color: color.to_owned(),
+                    duration,
+                })
+            }
+            "tint" => Some(ScreenEffect::tint(value.trim())),
+            "vignette" => Some(ScreenEffect::Vignette(value.trim().parse().ok()?)),
+            "clear" => Some(ScreenEffect::Clear),
+            _ => None,
+        }
+    }
+}
+
+/// state of the effects of one StoryPage, see use_screen_effects
+#[derive(Clone, Copy, PartialEq)]
+pub struct ScreenEffects {
+    shake: Signal<(f32, f32)>,
+    /// color, opacity, transition miliseconds
+    cover: Signal<Option<(String, f32, u32)>>,
+    tint: Signal<Option<String>>,
+    vignette: Signal<Option<f32>>,
+}
+impl ScreenEffects {
+    /// style for the shaking element
+    pub fn shake_style(&self) -> String {
+        let (x, y) = (self.shake)();
+        if x == 0. && y == 0. {
+            "".to_owned()
+        } else {
+            format!("transform: translate({x:.1}px, {y:.1}px);")
+        }
+    }
+    /// overlays for tint, vignette, flash and fade
+    pub fn overlays(&self) -> Element {
+        let base = "position: absolute;inset: 0;pointer-events: none;z-index: 1000;";
+        rsx! {
+            if let Some(color) = (self.tint)() {
+                div{
+                    class: "screen-tint",
+                    style: "{base}background-color: {color};",
+                }
+            }
+            if let Some(strength) = (self.vignette)() {
+                div{
+                    class: "screen-vignette",
+                    style: "{base}background: radial-gradient(ellipse at center, transparent {(1. - strength) * 60. + 20.}%, rgba(0, 0, 0, {strength}) 100%);",
+                }
+            }
+            if let Some((color, opacity, duration)) = (self.cover)() {
+                div{
+                    class: "screen-cover",
+                    style: "{base}background-color: {color};opacity: {opacity};transition: opacity {duration}ms;",
+                }
+            }
+        }
+    }
+
+    fn play(mut self, effect: ScreenEffect) {
+        let reduced_motion = TEXTCONFIG.peek().reduced_motion;
+        match effect {
+            ScreenEffect::Tint(color) => *self.tint.write() = Some(color),
+            ScreenEffect::Vignette(strength) => *self.vignette.write() = Some(strength),
+            ScreenEffect::Clear => {
+                *self.tint.write() = None;
+                *self.vignette.write() = None;
+            }
+            _ if reduced_motion => {}
+            ScreenEffect::Shake {
+                intensity,
+                duration,
+            } => {
+                // gen_range panics on the empty range of a negative or NaN power
+                let intensity = intensity.abs();
+                if !intensity.is_finite() || intensity == 0. {
+                    return;
+                }
+                spawn(async move {
+                    let mut time = 0;
+                    while time < duration {
+                        let power = intensity * (1. - time as f32 / duration as f32);
+                        let mut rng = thread_rng();
+                        *self.shake.write() =
+                            (rng.gen_range(-power..=power), rng.gen_range(-power..=power));
+                        wait(16).await;
+                        time += 16;
+                    }
+                    *self.shake.write() = (0., 0.);
+                });
+            }
+            ScreenEffect::Flash { color, duration } => {
+                spawn(async move {
+                    *self.cover.write() = Some((color.clone(), 1., 0));
+                    wait(16).await;
+                    *self.cover.write() = Some((color, 0., duration));
+                    wait(duration).await;
+                    *self.cover.write() = None;
+                });
+            }
+            ScreenEffect::Fade { color, duration } => {
+                spawn(async move {
+                    *self.cover.write() = Some((color.clone(), 0., 0));
+                    wait(16).await;
+                    *self.cover.write() = Some((color.clone(), 1., duration / 2));
+                    wait(duration / 2).await;
+                    *self.cover.write() = Some((color, 0., duration / 2));
+                    wait(duration / 2).await;
+                    *self.cover.write() = None;
+                });
+            }
+        }
+    }
+}
+
+/// plays the effects pushed to SCREEN_EFFECTS, a scene SceneView keeps hidden leaves them
+/// to the one on screen
+pub fn use_screen_effects() -> ScreenEffects {
+    let effects = ScreenEffects {
+        shake: use_signal(|| (0., 0.)),
+        cover: use_signal(|| None),
+        tint: use_signal(|| None),
+        vignette: use_signal(|| None),
+    };
+    let active = use_scene_active();
+    // the player is counted while its scene is shown
+    let counted = use_hook(|| {
+        let counted = Rc::new(Cell::new(false));
+        count_player(&counted, *active.peek());
+        counted
+    });
+    let dropped = Rc::clone(&counted);
+    use_drop(move || count_player(&dropped, false));
+    use_effect(move || {
+        let active = active();
+        count_player(&counted, active);
+        if !active || SCREEN_EFFECTS.read().is_empty() {
+            return;
+        }
+        let queue = std::mem::take(&mut *SCREEN_EFFECTS.write());
+        for effect in queue {
+            effects.play(effect);
+        }
+    });
+    effects
+}
+
+fn count_player(counted: &Cell<bool>, active: bool) {
+    if counted.replace(active) == active {
+        return;
+    }
+    PLAYERS.with(|p| {
+        p.set(if active {
+            p.get() + 1
+        } else {
+            p.get().saturating_sub(1)
+        })
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScreenEffect;
+
+    #[test]
+    fn screen_effect_parse_shake() {
+        assert_eq!(ScreenEffect::parse("shake(-5)"), None);
+        assert_eq!(ScreenEffect::parse("shake(NaN)"), None);
+        assert_eq!(ScreenEffect::parse("shake(inf, 300)"), None);
+        assert_eq!(
+            ScreenEffect::parse("shake(0)"),
+            Some(ScreenEffect::Shake {
+                intensity: 0.,
+                duration: 500
+            })
+        );
+    }
+
+    #[test]
+    fn screen_effect_without_player() {
+        // SCREEN_EFFECTS needs a runtime, it isn't touched without a player
+        ScreenEffect::shake(8.).trigger();
+    }
+
+    #[test]
+    fn screen_effect_parse() {
+        assert_eq!(
+            ScreenEffect::parse("shake(8, 300)"),
+            Some(ScreenEffect::Shake {
+                intensity: 8.,
+                duration: 300
+            })
+        );
+        assert_eq!(
+            ScreenEffect::parse("shake(8)"),
+            Some(ScreenEffect::shake(8.))
+        );
+        assert_eq!(
+            ScreenEffect::parse("tint(rgba(0, 0, 0, 0.3))"),
+            Some(ScreenEffect::tint("rgba(0, 0, 0, 0.3)"))
+        );
+        assert_eq!(
+            ScreenEffect::parse("fade(red, 2000)"),
+            Some(ScreenEffect::Fade {
+                color: "red".to_owned(),
+                duration: 2000
+            })
+        );
+        assert_eq!(
+            ScreenEffect::parse("flash()"),
+            Some(ScreenEffect::flash("white"))
+        );
+        assert_eq!(ScreenEffect::parse("clear"), Some(ScreenEffect::Clear));
+        assert_eq!(ScreenEffect::parse("explode(3)"), None);
+    }
+}
@@ -19,6 +19,8 @@ pub static AUDIO_MANAGER: GlobalSignal<AudioManager> = Signal::global(|| {
     manager
 });
 pub static MUSIC: GlobalSignal<Option<StaticSoundHandle>> = Signal::global(|| None);
+/// name in SOUND_EFFECTS of the music started by play_music, saved with the game
+pub static CURRENT_MUSIC: GlobalSignal<Option<String>> = Signal::global(|| None);
 pub static SOUND_EFFECTS: GlobalSignal<HashMap<String, Vec<u8>>> =
     Signal::global(|| HashMap::new());
 // const BASE_SAMPLE_RATE: f64 = 44100.0;
@@ -140,15 +142,40 @@ impl SoundEffect {
         info!("{}", AUDIO_MANAGER.write().num_sounds());
         let test = (*AUDIO_MANAGER.write()).play(sound)?;
         *MUSIC.write() = Some(test);
+        // unnamed music can't be restored, play_music sets the name after this
+        *CURRENT_MUSIC.write() = None;
 
         Ok(())
     }
 
+    /// play the music registered in SOUND_EFFECTS and remember its name
+    pub fn play_music(name: &str) -> Result<(), Box<dyn Error>> {
+        let data = SOUND_EFFECTS
+            .read()
+            .get(name)
+            .cloned()
+            .ok_or(format!("no sound named {name}"))?;
+        SoundEffect::new(data)?.music_play()?;
+        *CURRENT_MUSIC.write() = Some(name.to_owned());
+        Ok(())
+    }
+
+    /// play the sound effect registered in SOUND_EFFECTS, e.g. a voice clip
+    pub fn play_named(name: &str) -> Result<(), Box<dyn Error>> {
+        let data = SOUND_EFFECTS
+            .read()
+            .get(name)
+            .cloned()
+            .ok_or(format!("no sound named {name}"))?;
+        SoundEffect::new(data)?.play()
+    }
+
     pub fn music_stop() {
         if let Some(music) = MUSIC.write().as_mut() {
             let mut tween = Tween::default();
             tween.duration = Duration::from_millis(500);
             music.stop(tween);
         }
+        *CURRENT_MUSIC.write() = None;
     }
 }
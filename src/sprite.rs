@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+
+use dioxus::prelude::*;
+
+use crate::story_base::ImagePrint;
+
+/// Global variables for named sprite positions
+/// (x, y) in percent of the scene, measured from the bottom-left corner
+pub static SPRITE_SLOTS: GlobalSignal<HashMap<String, (f32, f32)>> = Signal::global(|| {
+    HashMap::from([
+        ("far-left".to_owned(), (10., 0.)),
+        ("left".to_owned(), (25., 0.)),
+        ("center".to_owned(), (50., 0.)),
+        ("right".to_owned(), (75., 0.)),
+        ("far-right".to_owned(), (90., 0.)),
+    ])
+});
+
+/// where a sprite stands on the scene
+#[derive(Clone, PartialEq, Debug)]
+pub enum Anchor {
+    /// named position from SPRITE_SLOTS, the sprite is centered on it
+    Slot(String),
+    /// x, y in percent of the scene from the bottom-left, the sprite is centered on x
+    Point(f32, f32),
+    /// rem offset from the left edge (the left_img layout)
+    Left(f32),
+    /// rem offset from the right edge (the right_img layout)
+    Right(f32),
+}
+
+/// placement and transform of one sprite
+/// # Example
+/// ```
+/// fn test() {
+///     let layout = SpriteLayout::default().slot("far-left").scale(1.2).flip(true).z_index(3);
+///     let story = Story::default().add_sprite(
+///         ImagePrint::new("image/alice.webp", "", "").layout(layout),
+///     );
+///     assert_eq!(story.layers().len(), 1);
+/// }
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct SpriteLayout {
+    pub anchor: Anchor,
+    pub scale: f32,
+    pub flip: bool,
+    /// degrees, clockwise
+    pub rotation: f32,
+    pub opacity: f32,
+    /// stacking order, the order of the sprite list is used when None
+    pub z_index: Option<i32>,
+}
+impl Default for SpriteLayout {
+    fn default() -> Self {
+        SpriteLayout {
+            anchor: Anchor::Slot("center".to_owned()),
+            scale: 1.,
+            flip: false,
+            rotation: 0.,
+            opacity: 1.,
+            z_index: None,
+        }
+    }
+}
+impl SpriteLayout {
+    pub fn anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+    pub fn slot(mut self, slot: &str) -> Self {
+        self.anchor = Anchor::Slot(slot.to_owned());
+        self
+    }
+    pub fn at(mut self, x: f32, y: f32) -> Self {
+        self.anchor = Anchor::Point(x, y);
+        self
+    }
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+    pub fn flip(mut self, flip: bool) -> Self {
+        self.flip = flip;
+        self
+    }
+    pub fn rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+    pub fn z_index(mut self, z_index: i32) -> Self {
+        self.z_index = Some(z_index);
+        self
+    }
+
+    /// layout of the index-th left_img
+    pub fn left(index: usize) -> Self {
+        SpriteLayout::default()
+            .anchor(Anchor::Left(index as f32 * 5. - 5.))
+            .z_index(index as i32)
+    }
+    /// layout of the index-th right_img, mirrored
+    pub fn right(index: usize) -> Self {
+        SpriteLayout::default()
+            .anchor(Anchor::Right(index as f32 * 5. - 5.))
+            .z_index(index as i32)
+            .flip(true)
+    }
+    /// layout of center_img
+    pub fn center() -> Self {
+        SpriteLayout::default().at(50., 0.)
+    }
+
+    /// css for the sprite, identity transforms and a bottom of 0 are left out so the image style can set them.
+    /// slots are the named positions, usually SPRITE_SLOTS
+    pub fn style(&self, slots: &HashMap<String, (f32, f32)>) -> String {
+        let mut transform = vec![];
+        let mut style = match &self.anchor {
+            Anchor::Slot(slot) => {
+                let (x, y) = slots.get(slot).copied().unwrap_or((50., 0.));
+                transform.push("translateX(-50%)".to_owned());
+                point_style(x, y)
+            }
+            Anchor::Point(x, y) => {
+                transform.push("translateX(-50%)".to_owned());
+                point_style(*x, *y)
+            }
+            Anchor::Left(rem) => format!("left: {rem}rem;"),
+            Anchor::Right(rem) => format!("right: {rem}rem;"),
+        };
+        if self.scale != 1. {
+            transform.push(format!("scale({})", self.scale));
+        }
+        if self.rotation != 0. {
+            transform.push(format!("rotate({}deg)", self.rotation));
+        }
+        if self.flip {
+            transform.push("scaleX(-1)".to_owned());
+        }
+        if let Some(z) = self.z_index {
+            style.push_str(&format!("z-index:{z};"));
+        }
+        if self.opacity != 1. {
+            style.push_str(&format!("opacity: {};", self.opacity));
+        }
+        if !transform.is_empty() {
+            style.push_str(&format!("transform: {};", transform.join(" ")));
+        }
+        style
+    }
+}
+
+fn point_style(x: f32, y: f32) -> String {
+    if y == 0. {
+        format!("left: {x}%;")
+    } else {
+        format!("left: {x}%;bottom: {y}%;")
+    }
+}
+
+/// a sprite with its resolved layout, see Story::layers
+#[derive(Clone, PartialEq, Debug)]
+pub struct SpriteLayer {
+    /// unique in a story, the character id or the image name
+    pub key: String,
+    pub image: ImagePrint,
+    pub layout: SpriteLayout,
+}
+
+/// screen edge a sprite slides from or to
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Edge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// how a sprite enters or leaves the scene
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SpriteTransition {
+    None,
+    Fade,
+    Slide(Edge),
+}
+impl SpriteTransition {
+    /// css for the hidden end of the transition
+    fn hidden_style(&self) -> &'static str {
+        match self {
+            SpriteTransition::None => "",
+            SpriteTransition::Fade => "opacity: 0;",
+            SpriteTransition::Slide(Edge::Left) => "translate: -100vw 0;",
+            SpriteTransition::Slide(Edge::Right) => "translate: 100vw 0;",
+            SpriteTransition::Slide(Edge::Top) => "translate: 0 -100vh;",
+            SpriteTransition::Slide(Edge::Bottom) => "translate: 0 100vh;",
+        }
+    }
+}
+
+/// transitions StoryPage plays when the sprites change between stories
+/// # Example
+/// ```
+/// fn test() {
+///     let transitions = SpriteTransitions::default()
+///         .enter(SpriteTransition::Slide(Edge::Left))
+///         .duration(500);
+///     assert_eq!(transitions.exit, SpriteTransition::Fade);
+/// }
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct SpriteTransitions {
+    pub enter: SpriteTransition,
+    pub exit: SpriteTransition,
+    /// animate sprites whose layout changed to the new slot
+    pub moving: bool,
+    /// miliseconds, skipped while fast-forwarding
+    pub duration: u32,
+}
+impl Default for SpriteTransitions {
+    fn default() -> Self {
+        SpriteTransitions {
+            enter: SpriteTransition::Fade,
+            exit: SpriteTransition::Fade,
+            moving: true,
+            duration: 300,
+        }
+    }
+}
+impl SpriteTransitions {
+    pub fn enter(mut self, enter: SpriteTransition) -> Self {
+        self.enter = enter;
+        self
+    }
+    pub fn exit(mut self, exit: SpriteTransition) -> Self {
+        self.exit = exit;
+        self
+    }
+    pub fn moving(mut self, moving: bool) -> Self {
+        self.moving = moving;
+        self
+    }
+    pub fn duration(mut self, duration: u32) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// css transition property for a sprite, none while fast-forwarding
+    pub(crate) fn transition_style(&self, ffward: bool) -> String {
+        let d = self.duration;
+        if ffward || d == 0 {
+            String::new()
+        } else if self.moving {
+            format!("transition: opacity {d}ms, translate {d}ms, left {d}ms, right {d}ms, bottom {d}ms, transform {d}ms;")
+        } else {
+            format!("transition: opacity {d}ms, translate {d}ms;")
+        }
+    }
+    pub(crate) fn enter_style(&self) -> &'static str {
+        self.enter.hidden_style()
+    }
+    pub(crate) fn exit_style(&self) -> &'static str {
+        self.exit.hidden_style()
+    }
+}
+
+/// difference of the sprites of two stories, matched by key
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct LayerDiff {
+    /// keys only in the new story
+    pub entered: Vec<String>,
+    /// layers only in the old story
+    pub exited: Vec<SpriteLayer>,
+    /// keys in both stories with a different layout
+    pub moved: Vec<String>,
+}
+
+/// compare the layers of the previous story and the current one
+pub fn diff_layers(before: &[SpriteLayer], after: &[SpriteLayer]) -> LayerDiff {
+    let mut diff = LayerDiff::default();
+    for layer in after {
+        match before.iter().find(|b| b.key == layer.key) {
+            None => diff.entered.push(layer.key.clone()),
+            Some(b) if b.layout != layer.layout => diff.moved.push(layer.key.clone()),
+            Some(_) => {}
+        }
+    }
+    diff.exited = before
+        .iter()
+        .filter(|b| !after.iter().any(|a| a.key == b.key))
+        .cloned()
+        .collect();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{diff_layers, Anchor, SpriteLayer, SpriteLayout, SpriteTransitions};
+    use crate::story_base::ImagePrint;
+
+    #[test]
+    fn legacy_layout_style() {
+        let slots = HashMap::new();
+        assert_eq!(
+            SpriteLayout::left(0).style(&slots),
+            "left: -5rem;z-index:0;"
+        );
+        assert_eq!(
+            SpriteLayout::right(2).style(&slots),
+            "right: 5rem;z-index:2;transform: scaleX(-1);"
+        );
+        assert_eq!(
+            SpriteLayout::center().style(&slots),
+            "left: 50%;transform: translateX(-50%);"
+        );
+    }
+
+    #[test]
+    fn free_layout_style() {
+        let slots = HashMap::from([("door".to_owned(), (80., 5.))]);
+        assert_eq!(
+            SpriteLayout::default().slot("door").style(&slots),
+            "left: 80%;bottom: 5%;transform: translateX(-50%);"
+        );
+        let layout = SpriteLayout::default()
+            .anchor(Anchor::Point(30., 10.))
+            .scale(1.5)
+            .rotation(-10.)
+            .opacity(0.5)
+            .z_index(4);
+        assert_eq!(
+            layout.style(&slots),
+            "left: 30%;bottom: 10%;z-index:4;opacity: 0.5;transform: translateX(-50%) scale(1.5) rotate(-10deg);"
+        );
+    }
+
+    #[test]
+    fn sprite_transition_ffward() {
+        let transitions = SpriteTransitions::default().duration(200);
+        assert!(transitions.transition_style(false).contains("left 200ms"));
+        assert_eq!(transitions.transition_style(true), "");
+        assert_eq!(transitions.duration(0).transition_style(false), "");
+    }
+
+    #[test]
+    fn layer_diff() {
+        let layer = |key: &str, layout: SpriteLayout| SpriteLayer {
+            key: key.to_owned(),
+            image: ImagePrint::new(key, "", ""),
+            layout,
+        };
+        let before = vec![
+            layer("alice", SpriteLayout::left(0)),
+            layer("bob", SpriteLayout::left(1)),
+            layer("desk", SpriteLayout::center()),
+        ];
+        let after = vec![
+            layer("alice", SpriteLayout::left(0)),
+            layer("bob", SpriteLayout::right(0)),
+            layer("carol", SpriteLayout::left(1)),
+        ];
+        let diff = diff_layers(&before, &after);
+        assert_eq!(diff.entered, vec!["carol".to_owned()]);
+        assert_eq!(diff.moved, vec!["bob".to_owned()]);
+        assert_eq!(diff.exited, vec![before[2].clone()]);
+    }
+}
@@ -1,12 +1,25 @@
 use std::fmt::Debug;
 
+use crate::background::{Background, BackgroundLayer, BackgroundTransition};
+use crate::clock::{clock_sleep, CLOCK};
+use crate::history::{History, RollbackSettings, Snapshot};
+use crate::input::{Gesture, InputAction, KeyBindings, TouchTracker};
+use crate::message_log::{LogEntry, MessageLog, MessageLogView};
+use crate::runner::{AdvanceCause, RunnerEvent, StoryEvent, StoryRunner, TickTimer};
+use crate::save::{SaveData, SaveSettings, READ_LINES_DELAY};
+use crate::scene::{use_scene_active, SceneChange};
+use crate::screen_effect::{use_screen_effects, ScreenEffect};
+use crate::sound_effect::SoundEffect;
+use crate::sprite::{diff_layers, SpriteLayer, SpriteLayout, SpriteTransitions, SPRITE_SLOTS};
 use crate::text_print::*;
 use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use tracing::info;
 // use tracing::info;
 // use web_sys::{AudioContext, AudioContextState};
 
-/// wait for milisecondes
+/// wait for milisecondes on the clock set with set_clock
 /// # Examples
 /// ```
 /// #[component]
@@ -23,52 +36,164 @@ use tracing::info;
 ///     }
 /// }
 /// ```
-#[cfg(target_arch = "wasm32")]
 pub async fn wait(mili: u32) {
-    use gloo_timers::future::TimeoutFuture;
-    TimeoutFuture::new(mili).await;
-}
-/// wait for milisecondes
-/// # Examples
-/// ```
-/// #[component]
-/// fn TestCounter() -> Element {
-///     let mut count = use_signal(|| 0);
-///     use_future(move || async move {
-///         loop {
-///             wait(1000).await;
-///             *count.write() += 1;
-///         }
-///     });
-///     rsx! {
-///         h1{"{count}"}
-///     }
-/// }
-/// ```
-#[cfg(not(target_arch = "wasm32"))]
-pub async fn wait(mili: u32) {
-    use std::time::Duration;
-    tokio::time::sleep(Duration::from_millis(mili as u64)).await;
+    clock_sleep(mili).await;
 }
 
-/// Global variables for routing operations
-/// (If you don't use the Route function to avoid being affected by url, it will be easier to upload on itch.io , etc.)
+/// Global variables for routing operations, StoryPage puts next here when it ends without a next_scene.
+/// SceneView is the scene manager, both work without url routing (easier to upload on itch.io, etc.)
 pub static GAMESTATE: GlobalSignal<Element> = Signal::global(|| rsx! {});
 /// Global variables for message Log
-pub static LOG: GlobalSignal<Vec<Vec<TextPrint>>> = Signal::global(|| vec![]);
+pub static LOG: GlobalSignal<MessageLog> = Signal::global(MessageLog::default);
+/// Global variables for the chapter and line shown by StoryPage
+pub static STORY_POSITION: GlobalSignal<StoryPosition> = Signal::global(StoryPosition::default);
+/// Global variables for the story shown by StoryPage
+pub static CURRENT_STORY: GlobalSignal<Option<Story>> = Signal::global(|| None);
+/// Global variables for the line ids the player has read, see Story::line_id
+pub static READ_LINES: GlobalSignal<HashSet<String>> = Signal::global(HashSet::new);
 /// Global variables for text config
 pub static TEXTCONFIG: GlobalSignal<TextConfig> = Signal::global(|| TextConfig {
     sound_volum: 1.,
     music_volum: 1.,
     speed: 1.,
     auto_speed: 5000,
-    is_auto: false,
-    is_ffward: false,
-    is_close: false,
-    is_setting: false,
-    is_log: false,
+    reduced_motion: false,
+    skip_unread: false,
+    key_bindings: KeyBindings::default(),
 });
 
+/// chapter name given to StoryPage, the index in its storys and the line id of that story
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug, Default)]
+pub struct StoryPosition {
+    pub chapter: String,
+    pub index: usize,
+    /// Story::line_id, empty when unknown
+    #[serde(default)]
+    pub line_id: String,
+}
+impl StoryPosition {
+    /// index of the line in storys.
+    /// the line id is searched among the ids assign_line_ids gives with the chapter,
+    /// so raw storys find the lines StoryPage saved. the nearest match to index wins
+    /// when an id repeats. without a line id the index is trusted
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     let storys = vec![
+    ///         Story::default().msg(TextPrint::parse("{{}}new line".to_owned())),
+    ///         Story::default().msg(TextPrint::parse("{{}}hello".to_owned())),
+    ///     ];
+    ///     let position = StoryPosition {
+    ///         chapter: "prologue".to_owned(),
+    ///         index: 0,
+    ///         line_id: derive_line_id("prologue", "", "hello"),
+    ///     };
+    ///     assert_eq!(position.find(&storys), Some(1));
+    /// }
+    /// ```
+    pub fn find(&self, storys: &[Story]) -> Option<usize> {
+        if self.line_id.is_empty() {
+            return (self.index < storys.len()).then_some(self.index);
+        }
+        let assigned = assign_line_ids(&self.chapter, storys.to_vec()).ok();
+        let nearest = |storys: &[Story]| {
+            storys
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| s.line_id() == self.line_id)
+                .map(|(i, _)| i)
+                .min_by_key(|i| i.abs_diff(self.index))
+        };
+        // ids saved without a chapter, e.g. from a LightMessageBox without one
+        assigned
+            .as_deref()
+            .and_then(nearest)
+            .or_else(|| nearest(storys))
+    }
+}
+
+/// 64 bit FNV-1a, unlike DefaultHasher it doesn't change between rust versions
+fn stable_hash(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// line id from a hash of chapter, speaker and text, make_story_file uses the same hash
+pub fn derive_line_id(chapter: &str, speaker: &str, text: &str) -> String {
+    let source = if chapter.is_empty() {
+        format!("{speaker}\n{text}")
+    } else {
+        format!("{chapter}\n{speaker}\n{text}")
+    };
+    format!("{:016x}", stable_hash(&source))
+}
+
+/// two stories of a chapter were given the same id
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DuplicateLineId {
+    pub id: String,
+    pub first: usize,
+    pub second: usize,
+}
+impl std::fmt::Display for DuplicateLineId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line id {:?} is used by story {} and {}",
+            self.id, self.first, self.second
+        )
+    }
+}
+impl std::error::Error for DuplicateLineId {}
+
+/// give every story without an id one derived from the chapter, speaker and text.
+/// repeated lines and hash collisions get a -2, -3 .. suffix, repeated explicit ids are an error
+/// # Example
+/// ```
+/// fn test() -> Result<(), DuplicateLineId> {
+///     let storys = assign_line_ids("prologue", vec![
+///         Story::default().msg(TextPrint::parse("{{}}...".to_owned())),
+///         Story::default().msg(TextPrint::parse("{{}}...".to_owned())),
+///     ])?;
+///     assert_eq!(storys[1].line_id(), format!("{}-2", storys[0].line_id()));
+///     Ok(())
+/// }
+/// ```
+pub fn assign_line_ids(
+    chapter: &str,
+    mut storys: Vec<Story>,
+) -> Result<Vec<Story>, DuplicateLineId> {
+    let mut used: HashMap<String, usize> = HashMap::new();
+    for (index, story) in storys.iter().enumerate() {
+        if story.id.is_empty() {
+            continue;
+        }
+        if let Some(first) = used.insert(story.id.clone(), index) {
+            return Err(DuplicateLineId {
+                id: story.id.clone(),
+                first,
+                second: index,
+            });
+        }
+    }
+    for (index, story) in storys.iter_mut().enumerate() {
+        if !story.id.is_empty() {
+            continue;
+        }
+        let base = derive_line_id(chapter, &story.speaker_id(), &story.text());
+        let mut id = base.clone();
+        let mut count = 1;
+        while used.contains_key(&id) {
+            count += 1;
+            id = format!("{base}-{count}");
+        }
+        used.insert(id.clone(), index);
+        story.id = id;
+    }
+    Ok(storys)
+}
+
 /// a number of settings involved in the output of text
 #[derive(Debug, PartialEq, Clone)]
 pub struct TextConfig {
@@ -80,31 +205,78 @@ pub struct TextConfig {
     pub speed: f32,
     /// auto next working, miliseconds
     pub auto_speed: u32,
+    /// skip shake, flash and fade screen effects
+    pub reduced_motion: bool,
+    /// the skip button doesn't stop at lines the player hasn't read
+    pub skip_unread: bool,
+    pub key_bindings: KeyBindings,
+}
 
-    /// other state value
+/// # BoxState
+/// The state of one message box, the player's preferences stay in TEXTCONFIG.
+/// StoryPage and LightMessageBox each provide their own to the components inside them,
+/// so two speech balloons on a screen don't share auto or close.
+/// ## Example
+/// ```
+/// #[component]
+/// fn OpenLog() -> Element {
+///     // inside other_setting, or any component under a StoryPage
+///     let mut state = use_box_state();
+///     rsx! {
+///         button{
+///             onclick: move |_| {
+///                 let mut state = state.write();
+///                 state.is_setting = false;
+///                 state.is_log = true;
+///             },
+///             "log"
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct BoxState {
     pub is_auto: bool,
-    /// other state value
     pub is_ffward: bool,
-    /// other state value
+    /// the box is hidden until the screen is clicked
     pub is_close: bool,
-    /// other state value
     pub is_setting: bool,
-    /// other state value
     pub is_log: bool,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+/// the BoxState of the closest StoryPage or LightMessageBox,
+/// a component outside of them gets a state of its own
+pub fn use_box_state() -> Signal<BoxState> {
+    use_hook(|| try_consume_context().unwrap_or_else(|| Signal::new(BoxState::default())))
+}
+
+/// the wheel or right button action of StoryPage, its StoryBox does it like a key
+#[derive(Clone, Copy)]
+struct PendingAction(Signal<Option<InputAction>>);
+
+/// not Eq since 0.2, the f32 of its layout can't be
+#[derive(Clone, PartialEq, Debug)]
 pub struct ImagePrint {
     pub style: String,
     pub name: String,
     pub class: String,
+    /// character id used for speaker highlighting (empty is not a character)
+    pub character: String,
+    /// free placement, overrides the left/center/right layout
+    pub layout: Option<SpriteLayout>,
 }
 impl ImagePrint {
     pub fn new(name: &str, style: &str, class: &str) -> Self {
         let name = name.to_owned();
         let style = style.to_owned();
         let class = class.to_owned();
-        ImagePrint { name, style, class }
+        ImagePrint {
+            name,
+            style,
+            class,
+            character: "".to_owned(),
+            layout: None,
+        }
     }
     pub fn name(mut self, name: &str) -> Self {
         self.name = name.to_owned();
@@ -118,6 +290,78 @@ impl ImagePrint {
         self.class = class.to_owned();
         self
     }
+    /// tag the image with a character id
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     let image = ImagePrint::new("image/alice.webp", "", "").character("alice");
+    ///     assert_eq!(image.character.as_str(), "alice");
+    /// }
+    /// ```
+    pub fn character(mut self, character: &str) -> Self {
+        self.character = character.to_owned();
+        self
+    }
+    pub fn layout(mut self, layout: SpriteLayout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+}
+
+/// How StoryPage marks the sprite of the current speaker
+/// ## Example
+/// ```
+/// fn test() {
+///     let highlight = SpeakerHighlight::default().dim_filter("grayscale(0.5)");
+///     assert_eq!(highlight.speaking_class.as_str(), "speaking");
+/// }
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct SpeakerHighlight {
+    /// class added to the sprites of the speaker
+    pub speaking_class: String,
+    /// css filter for the sprites of the other characters
+    pub dim_filter: String,
+    /// class added to the speaker's sprites on the first line after the speaker changes
+    pub bounce_class: Option<String>,
+}
+impl Default for SpeakerHighlight {
+    fn default() -> Self {
+        SpeakerHighlight {
+            speaking_class: "speaking".to_owned(),
+            dim_filter: "brightness(0.6)".to_owned(),
+            bounce_class: Some("speaker-bounce".to_owned()),
+        }
+    }
+}
+impl SpeakerHighlight {
+    pub fn speaking_class(mut self, class: &str) -> Self {
+        self.speaking_class = class.to_owned();
+        self
+    }
+    pub fn dim_filter(mut self, filter: &str) -> Self {
+        self.dim_filter = filter.to_owned();
+        self
+    }
+    pub fn bounce_class(mut self, class: Option<&str>) -> Self {
+        self.bounce_class = class.map(|c| c.to_owned());
+        self
+    }
+    /// extra (class, style) for a sprite.
+    /// images without a character and lines without a speaker are left as they are
+    fn apply(&self, image: &ImagePrint, speaker: &str, speaker_changed: bool) -> (String, String) {
+        if image.character.is_empty() || speaker.is_empty() {
+            (String::new(), String::new())
+        } else if image.character == speaker {
+            let class = match (&self.bounce_class, speaker_changed) {
+                (Some(bounce), true) => format!("{} {}", self.speaking_class, bounce),
+                _ => self.speaking_class.clone(),
+            };
+            (class, String::new())
+        } else {
+            (String::new(), format!("filter: {};", self.dim_filter))
+        }
+    }
 }
 
 /// # Story
@@ -154,8 +398,21 @@ pub struct Story {
     pub center_img: Option<ImagePrint>,
     pub left_img: Vec<ImagePrint>,
     pub right_img: Vec<ImagePrint>,
-    pub background: String,
+    /// a String of css before 0.2, `Background::from(style)` converts one
+    pub background: Background,
     pub class: String,
+    /// character id of the speaker, the title text is used when empty
+    pub speaker: String,
+    /// freely placed sprites, drawn after left, center and right images
+    pub sprites: Vec<ImagePrint>,
+    /// screen effects played when the story is shown
+    pub effects: Vec<ScreenEffect>,
+    /// the game shows a choice on this story, auto-saved when SaveSettings::autosave_choice is set
+    pub choice_point: bool,
+    /// stable line id, derived from the speaker and message when empty, see line_id
+    pub id: String,
+    /// name in SOUND_EFFECTS of the voice clip played with the line
+    pub voice: Option<String>,
 }
 impl Default for Story {
     fn default() -> Self {
@@ -165,8 +422,14 @@ impl Default for Story {
             center_img: None,
             left_img: vec![],
             right_img: vec![],
-            background: "".to_owned(),
+            background: Background::default(),
             class: "".to_owned(),
+            speaker: "".to_owned(),
+            sprites: vec![],
+            effects: vec![],
+            choice_point: false,
+            id: "".to_owned(),
+            voice: None,
         }
     }
 }
@@ -180,7 +443,7 @@ impl Story {
         background: &str,
         class: &str,
     ) -> Self {
-        let background = background.to_owned();
+        let background = Background::from(background);
         let class = class.to_owned();
         Story {
             title,
@@ -190,6 +453,12 @@ impl Story {
             right_img,
             background,
             class,
+            speaker: "".to_owned(),
+            sprites: vec![],
+            effects: vec![],
+            choice_point: false,
+            id: "".to_owned(),
+            voice: None,
         }
     }
 
@@ -364,11 +633,71 @@ impl Story {
     /// ```
     /// fn test() {
     ///     let story_base = Story::default().background("width:100%;");
-    ///     assert_eq!(story_base.background.as_str(), "width:100%;");
+    ///     assert_eq!(story_base.background.style.as_str(), "width:100%;");
     /// }
     /// ```
     pub fn background(mut self, background: &str) -> Self {
-        self.background = background.to_owned();
+        self.background.style = background.to_owned();
+        self
+    }
+    /// change story background, the style is kept when the new one has none
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     let story_base = Story::default()
+    ///         .background("width:100%;")
+    ///         .backdrop(Background::color("black"));
+    ///     assert_eq!(story_base.background.style.as_str(), "width:100%;");
+    ///     assert_eq!(story_base.background.color.as_deref(), Some("black"));
+    /// }
+    /// ```
+    pub fn backdrop(mut self, mut background: Background) -> Self {
+        if background.style.is_empty() {
+            background.style = std::mem::take(&mut self.background.style);
+        }
+        self.background = background;
+        self
+    }
+    /// change story background image
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     let story_base = Story::default().background_image("image/school.webp");
+    ///     assert_eq!(story_base.background.image.as_deref(), Some("image/school.webp"));
+    /// }
+    /// ```
+    pub fn background_image(mut self, image: &str) -> Self {
+        self.background.image = Some(image.to_owned());
+        self
+    }
+    /// change story background color
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     let story_base = Story::default().background_color("#202030");
+    ///     assert_eq!(story_base.background.color.as_deref(), Some("#202030"));
+    /// }
+    /// ```
+    pub fn background_color(mut self, color: &str) -> Self {
+        self.background.color = Some(color.to_owned());
+        self
+    }
+    /// change how the background appears when it differs from the last story
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     let story_base =
+    ///         Story::default().background_transition(BackgroundTransition::FadeThroughBlack, 1000);
+    ///     assert_eq!(story_base.background.duration, 1000);
+    /// }
+    /// ```
+    pub fn background_transition(
+        mut self,
+        transition: BackgroundTransition,
+        duration: u32,
+    ) -> Self {
+        self.background.transition = transition;
+        self.background.duration = duration;
         self
     }
     /// change story background class
@@ -416,9 +745,234 @@ impl Story {
         self.center_img = None;
         self
     }
+    /// add freely placed sprite
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     let story_base = Story::default().add_sprite(
+    ///         ImagePrint::new("image/desk.webp", "", "").layout(SpriteLayout::default().at(80., 5.)),
+    ///     );
+    ///     assert_eq!(story_base.sprites[0].name.as_str(), "image/desk.webp");
+    /// }
+    /// ```
+    pub fn add_sprite(mut self, img: ImagePrint) -> Self {
+        self.sprites.push(img);
+        self
+    }
+    /// remove index freely placed sprite
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     let story_base = Story::default()
+    ///         .add_sprite(ImagePrint::new("image/desk.webp", "", ""))
+    ///         .remove_sprite(0);
+    ///     assert_eq!(story_base.sprites, vec![]);
+    /// }
+    /// ```
+    pub fn remove_sprite(mut self, index: usize) -> Self {
+        self.sprites.remove(index);
+        self
+    }
+    /// add screen effect played when the story is shown.
+    /// tint and vignette stay until a Clear effect
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     let story_base = Story::default().add_effect(ScreenEffect::flash("white"));
+    ///     assert_eq!(story_base.effects, vec![ScreenEffect::flash("white")]);
+    /// }
+    /// ```
+    pub fn add_effect(mut self, effect: ScreenEffect) -> Self {
+        self.effects.push(effect);
+        self
+    }
+    /// remove every screen effect
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     let story_base = Story::default()
+    ///         .add_effect(ScreenEffect::flash("white"))
+    ///         .clear_effects();
+    ///     assert_eq!(story_base.effects, vec![]);
+    /// }
+    /// ```
+    pub fn clear_effects(mut self) -> Self {
+        self.effects.clear();
+        self
+    }
+    /// mark the story as a choice point
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     let story_base = Story::default().choice_point(true);
+    ///     assert!(story_base.choice_point);
+    /// }
+    /// ```
+    pub fn choice_point(mut self, choice_point: bool) -> Self {
+        self.choice_point = choice_point;
+        self
+    }
+    /// voice clip played when the story is shown, the log can replay it
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     let story_base = Story::default().voice("alice-001");
+    ///     assert_eq!(story_base.voice.as_deref(), Some("alice-001"));
+    /// }
+    /// ```
+    pub fn voice(mut self, voice: &str) -> Self {
+        self.voice = Some(voice.to_owned());
+        self
+    }
+    /// change story line id
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     let story_base = Story::default().id("prologue-001");
+    ///     assert_eq!(story_base.line_id().as_str(), "prologue-001");
+    /// }
+    /// ```
+    pub fn id(mut self, id: &str) -> Self {
+        self.id = id.to_owned();
+        self
+    }
+    /// identifier of the line that stays when lines are added around it.
+    /// the id when it is set, otherwise a hash of the speaker and the message.
+    /// StoryPage, LightMessageBox and saves use the ids assign_line_ids gives with the chapter,
+    /// which also tells repeated lines apart, so this is only the same without a chapter
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     let hello = Story::default().msg(TextPrint::parse("{{}}hello".to_owned()));
+    ///     assert_eq!(hello.line_id(), hello.clone().class("other").line_id());
+    /// }
+    /// ```
+    pub fn line_id(&self) -> String {
+        if self.id.is_empty() {
+            derive_line_id("", &self.speaker_id(), &self.text())
+        } else {
+            self.id.clone()
+        }
+    }
+    /// the player has read the line
+    pub fn is_read(&self) -> bool {
+        READ_LINES.read().contains(&self.line_id())
+    }
+    /// the skip button may pass the line: it's read or skip_unread is set, and it's not a choice point
+    pub fn is_skippable(&self) -> bool {
+        !self.choice_point && (TEXTCONFIG.read().skip_unread || self.is_read())
+    }
+    /// the message without formatting
+    pub fn text(&self) -> String {
+        self.msg.iter().map(|t| t.msg.as_str()).collect()
+    }
+    /// speaker and first line of the message, e.g. for save slots
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     let story_base = Story::default()
+    ///         .speaker("alice")
+    ///         .msg(TextPrint::parse("{{}}hello\nworld".to_owned()));
+    ///     assert_eq!(story_base.preview().as_str(), "alice: hello");
+    /// }
+    /// ```
+    pub fn preview(&self) -> String {
+        let msg = self.text();
+        let line = msg.lines().next().unwrap_or("").trim();
+        let speaker = self.speaker_id();
+        if speaker.is_empty() {
+            line.to_owned()
+        } else {
+            format!("{speaker}: {line}")
+        }
+    }
+    /// every image of the story with its layout, in drawing order.
+    /// left, center and right images keep their classic layout unless they have their own
+    pub fn layers(&self) -> Vec<SpriteLayer> {
+        let left = self.left_img.iter().enumerate().map(|(index, img)| {
+            (
+                img,
+                img.layout
+                    .clone()
+                    .unwrap_or_else(|| SpriteLayout::left(index)),
+            )
+        });
+        let center = self
+            .center_img
+            .iter()
+            .map(|img| (img, img.layout.clone().unwrap_or_else(SpriteLayout::center)));
+        let right = self.right_img.iter().enumerate().map(|(index, img)| {
+            (
+                img,
+                img.layout
+                    .clone()
+                    .unwrap_or_else(|| SpriteLayout::right(index)),
+            )
+        });
+        let sprites = self
+            .sprites
+            .iter()
+            .map(|img| (img, img.layout.clone().unwrap_or_default()));
+
+        let mut layers: Vec<SpriteLayer> = vec![];
+        for (image, layout) in left.chain(center).chain(right).chain(sprites) {
+            let base = if image.character.is_empty() {
+                image.name.clone()
+            } else {
+                image.character.clone()
+            };
+            let mut key = base.clone();
+            let mut count = 1;
+            while layers.iter().any(|l| l.key == key) {
+                count += 1;
+                key = format!("{base}#{count}");
+            }
+            layers.push(SpriteLayer {
+                key,
+                image: image.clone(),
+                layout,
+            });
+        }
+        layers
+    }
+    /// change story speaker id
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     let story_base = Story::default().speaker("alice");
+    ///     assert_eq!(story_base.speaker_id().as_str(), "alice");
+    /// }
+    /// ```
+    pub fn speaker(mut self, speaker: &str) -> Self {
+        self.speaker = speaker.to_owned();
+        self
+    }
+    /// the speaker id, or the title text when no speaker was set
+    /// # Example
+    /// ```
+    /// fn test() {
+    ///     let story_base = Story::default().title(TextPrint::parse("{{}}alice".to_owned()));
+    ///     assert_eq!(story_base.speaker_id().as_str(), "alice");
+    /// }
+    /// ```
+    pub fn speaker_id(&self) -> String {
+        if self.speaker.is_empty() {
+            self.title
+                .iter()
+                .map(|t| t.msg.as_str())
+                .collect::<String>()
+                .trim()
+                .to_owned()
+        } else {
+            self.speaker.clone()
+        }
+    }
 }
 
-/// The ability to print Vec<Story> in order and move to the next page at the end of the story
+/// The ability to print Vec<Story> in order and move to the next page at the end of the story.
+/// Every sprite is an img in one `article.image`, ordered by z-index. Before sprite layouts
+/// the left, center and right images had an article each, css written for those
+/// three articles has to select the img classes or the styles of SpriteLayout instead.
 /// ## Example
 /// ```
 /// #[component]
@@ -426,13 +980,26 @@ impl Story {
 ///     rsx! {
 ///         StoryPage{
 ///             storys: vec![/*some story*/],
-///             next: rsx!{},
+///             next_scene: SceneChange::Replace("chapter-1".to_owned()), // optional, the scene after the last story,
+///                                                                       // next is put in GAMESTATE without it
 ///             on_next: move |_| {
 ///                 // next story will call this closure
 ///             },
-///             skip_len: 0, // you can setting to max skip index
+///             skip_len: 0, // lines before this index are skipped even if unread
 ///             skip: 0, // you can setting to start story index
-///             other_setting: rsx!{} // you can add other setting component
+///             other_setting: rsx!{}, // you can add other setting component
+///             speaker_highlight: SpeakerHighlight::default(), // optional, dims the sprites of non-speakers
+///             sprite_transitions: SpriteTransitions::default(), // optional, animates sprites between stories
+///             chapter: "prologue", // optional, name kept in STORY_POSITION for save data
+///             save: SaveSettings::default(), // optional, quick-save, quick-load and auto-save
+///             on_load: move |data: SaveData| {
+///                 // optional, a save of another chapter was quick-loaded, it is not restored yet
+///             },
+///             rollback: RollbackSettings::default(), // optional, wheel up or PageUp goes back a line,
+///                                                    // allow_choices also applies to "jump here" in the log
+///             on_event: move |event: StoryEvent| {
+///                 // optional, lines started and completed, advances, log and settings opened
+///             },
 ///         }
 ///     }
 /// }
@@ -440,19 +1007,279 @@ impl Story {
 #[component]
 pub fn StoryPage(
     storys: Vec<Story>,
-    next: Element,
+    #[props(default)] next: Element,
+    next_scene: Option<SceneChange>,
     on_next: EventHandler<DummyData>,
     skip_len: usize,
     skip: usize,
     other_setting: Element,
+    speaker_highlight: Option<SpeakerHighlight>,
+    sprite_transitions: Option<SpriteTransitions>,
+    #[props(default)] chapter: String,
+    save: Option<SaveSettings>,
+    on_load: Option<EventHandler<SaveData>>,
+    rollback: Option<RollbackSettings>,
+    on_event: Option<EventHandler<StoryEvent>>,
 ) -> Element {
+    let mut state = use_context_provider(|| Signal::new(BoxState::default()));
+    let mut pending = use_context_provider(|| PendingAction(Signal::new(None))).0;
     let mut story_index = use_signal(|| skip);
-    let story = use_memo(move || storys.get(story_index()).cloned());
+    let mut history = use_signal(|| History::new(rollback.map_or(0, |r| r.limit)));
+    // repeated lines get their own ids, so reading one doesn't mark the others as read
+    let storys = use_memo(use_reactive((&chapter, &storys), |(chapter, storys)| {
+        assign_line_ids(&chapter, storys.clone()).unwrap_or_else(|e| {
+            tracing::warn!("{e}");
+            storys
+        })
+    }));
+    let speaker_changed = use_memo(move || {
+        let index = story_index();
+        let storys = storys.read();
+        match (
+            storys.get(index),
+            index.checked_sub(1).and_then(|i| storys.get(i)),
+        ) {
+            (Some(now), Some(before)) => now.speaker_id() != before.speaker_id(),
+            (Some(_), None) => true,
+            _ => false,
+        }
+    });
+    let story = use_memo(move || storys.read().get(story_index()).cloned());
+    let skippable = use_memo(move || story().is_some_and(|s| s.is_skippable()));
+    let read_save = save.clone();
+    use_hook(move || {
+        if let Some(save) = read_save {
+            if READ_LINES.peek().is_empty() {
+                save.load_read_lines();
+            }
+            if !SETTINGS_LOADED.replace(true) {
+                save.load_settings();
+            }
+        }
+    });
+    // a closed tab doesn't drop the page, so the read lines and settings are written when they change
+    let read_save = save.clone();
+    let mut read_changes = use_signal(|| 0_usize);
+    use_effect(move || {
+        READ_LINES.read();
+        TEXTCONFIG.read();
+        let Some(save) = read_save.clone() else {
+            return;
+        };
+        *read_changes.write() += 1;
+        let count = *read_changes.peek();
+        // they were just loaded
+        if count == 1 {
+            return;
+        }
+        spawn(async move {
+            wait(READ_LINES_DELAY).await;
+            if *read_changes.peek() == count {
+                save.save_read_lines();
+                save.save_settings();
+            }
+        });
+    });
+    let read_save = save.clone();
+    use_drop(move || {
+        if let Some(save) = read_save {
+            save.save_read_lines();
+            save.save_settings();
+        }
+    });
+    let active = use_scene_active();
+    let position = use_memo(use_reactive((&chapter,), move |(chapter,)| StoryPosition {
+        chapter,
+        index: story_index(),
+        line_id: story().map_or_else(String::new, |s| s.line_id()),
+    }));
+    // a page under a pushed scene leaves these to the one on screen, and takes them back on pop
+    use_effect(move || {
+        if active() {
+            *STORY_POSITION.write() = position();
+            *CURRENT_STORY.write() = story();
+        }
+    });
+    let mut advances = use_signal(|| None::<u32>);
+    // set by on_next, a rollback, jump or load moves the line without being an advance
+    let mut forward = use_signal(|| true);
+    let autosave = save.clone();
+    use_effect(move || {
+        let position = position();
+        if !*active.peek() || !*forward.peek() {
+            return;
+        }
+        forward.set(false);
+        if let (Some(_), Some(s)) = (rollback, story()) {
+            history
+                .write()
+                .push(Snapshot::capture(position, s.choice_point));
+        }
+
+        // the first story shown is not an advance
+        let count = advances.peek().map_or(0, |c| c + 1);
+        *advances.write() = Some(count);
+        if let (Some(save), Some(s)) = (&autosave, story()) {
+            let every = save.autosave_every;
+            if (save.autosave_choice && s.choice_point)
+                || (every != 0 && count != 0 && count % every == 0)
+            {
+                save.auto_save();
+            }
+        }
+    });
+    let jump_chapter = chapter.clone();
+    let jump = move |snapshot: Snapshot| {
+        if snapshot.position.chapter != jump_chapter {
+            tracing::warn!("can't jump to chapter {}", snapshot.position.chapter);
+            return;
+        }
+        let allow_choices = rollback.is_some_and(|r| r.allow_choices);
+        let passed_choice = LOG
+            .peek()
+            .since(snapshot.log_end)
+            .any(|e| e.snapshot.as_ref().is_some_and(|s| s.choice_point));
+        if passed_choice && !allow_choices {
+            return;
+        }
+        let Some(index) = snapshot.position.find(&storys.peek()) else {
+            tracing::warn!("line {} is gone", snapshot.position.line_id);
+            return;
+        };
+        snapshot.restore();
+        history.write().rewind_to(&snapshot.position);
+        {
+            let mut state = state.write();
+            state.is_log = false;
+            state.is_auto = false;
+        }
+        *story_index.write() = index;
+    };
+    let page_chapter = chapter.clone();
+    let migrate = save.as_ref().and_then(|s| s.migrate.clone());
+    // nothing is restored when the saved line can't be found
+    let load = move |data: SaveData| {
+        if data.position.chapter == page_chapter {
+            match data.resolve(&storys.peek(), migrate.as_deref()) {
+                Ok(index) => {
+                    data.restore();
+                    history.write().clear();
+                    *story_index.write() = index;
+                }
+                Err(e) => {
+                    tracing::warn!("can't load the save: {e}");
+                    if let Some(on_event) = on_event {
+                        on_event.call(StoryEvent::LoadFailed(e));
+                    }
+                }
+            }
+        } else if let Some(on_load) = on_load {
+            on_load.call(data);
+        } else {
+            tracing::warn!(
+                "loaded a save of chapter {}, set on_load to route to it",
+                data.position.chapter
+            );
+        }
+    };
+    let mut step_back = move || {
+        if state.peek().is_log || state.peek().is_setting {
+            return;
+        }
+        let Some(rollback) = rollback else {
+            state.write().is_log = true;
+            if let Some(on_event) = on_event {
+                on_event.call(StoryEvent::LogOpened);
+            }
+            return;
+        };
+        let snapshot = history.write().rollback(rollback.allow_choices);
+        if let Some(snapshot) = snapshot {
+            snapshot.restore();
+            state.write().is_auto = false;
+            *story_index.write() = snapshot.position.index;
+        }
+    };
+    let speaker = story().map_or_else(String::new, |s| s.speaker_id());
+    let mut shown_layers = use_signal(Vec::<SpriteLayer>::new);
+    let mut entering = use_signal(HashSet::<String>::new);
+    let mut exiting = use_signal(Vec::<SpriteLayer>::new);
+    // exiting layers are drawn visible for a frame like entering ones are drawn hidden
+    let mut exit_started = use_signal(|| false);
+    let mut transition_count = use_signal(|| 0_usize);
+    let transitions = sprite_transitions.clone();
+    use_effect(move || {
+        let layers = story().map_or_else(Vec::new, |s| s.layers());
+        let diff = diff_layers(&shown_layers.peek(), &layers);
+        *shown_layers.write() = layers;
+        let Some(transitions) = transitions.clone() else {
+            return;
+        };
+        if state.peek().is_ffward || transitions.duration == 0 {
+            entering.write().clear();
+            exiting.write().clear();
+            return;
+        }
+        *entering.write() = diff.entered.into_iter().collect();
+        *exiting.write() = diff.exited;
+        exit_started.set(false);
+        *transition_count.write() += 1;
+        let count = *transition_count.peek();
+        spawn(async move {
+            // one frame with the starting style so the browser has a start point
+            wait(16).await;
+            entering.write().clear();
+            if *transition_count.peek() == count {
+                exit_started.set(true);
+            }
+            wait(transitions.duration).await;
+            if *transition_count.peek() == count {
+                exiting.write().clear();
+            }
+        });
+    });
+    let screen_effects = use_screen_effects();
+    use_effect(move || {
+        if let Some(s) = story() {
+            for effect in s.effects {
+                effect.trigger();
+            }
+            if let Some(voice) = s.voice {
+                if !state.peek().is_ffward && TEXTCONFIG.peek().sound_volum != 0. {
+                    if let Err(e) = SoundEffect::play_named(&voice) {
+                        tracing::warn!("can't play voice {voice}: {e}");
+                    }
+                }
+            }
+        }
+    });
+    let sprite_style = move |layer: &SpriteLayer, is_exit: bool| {
+        sprite_transitions.as_ref().map_or_else(String::new, |t| {
+            let hidden = if is_exit {
+                if exit_started() {
+                    t.exit_style()
+                } else {
+                    ""
+                }
+            } else if entering.read().contains(&layer.key) {
+                t.enter_style()
+            } else {
+                ""
+            };
+            format!("{}{}", t.transition_style(state.read().is_ffward), hidden)
+        })
+    };
+    let highlight = move |image: &ImagePrint| {
+        speaker_highlight.as_ref().map_or_else(
+            || (String::new(), String::new()),
+            |h| h.apply(image, &speaker, speaker_changed()),
+        )
+    };
 
     let background = if let Some(s) = story() {
         s.background
     } else {
-        "".to_string()
+        Background::default()
     };
     let class = if let Some(s) = story() {
         s.class
@@ -460,60 +1287,77 @@ pub fn StoryPage(
         "".to_string()
     };
 
-    if story().is_none() {
-        *GAMESTATE.write() = next.clone();
-    }
+    let mut finished = use_signal(|| false);
+    use_effect(use_reactive(
+        (&next, &next_scene),
+        move |(next, next_scene)| {
+            let done = story().is_none();
+            if done == *finished.peek() {
+                return;
+            }
+            finished.set(done);
+            if !done {
+                return;
+            }
+            if let Some(on_event) = on_event {
+                on_event.call(StoryEvent::Finished);
+            }
+            match next_scene {
+                Some(change) => change.apply(),
+                None => *GAMESTATE.write() = next,
+            }
+        },
+    ));
 
     rsx! {
         main{
-            style: "{background}",
+            style: "{background.style}",
             class: "{class}",
+            prevent_default: "oncontextmenu",
             onclick: move |_| {
-                TEXTCONFIG.write().is_close = false;
+                state.write().is_close = false;
+            },
+            oncontextmenu: move |_| {
+                if let Some(action) = TEXTCONFIG.peek().key_bindings.right_click {
+                    pending.set(Some(action));
+                }
+            },
+            onwheel: move |e| {
+                if e.delta().strip_units().y >= 0. {
+                    return;
+                }
+                if let Some(action) = TEXTCONFIG.peek().key_bindings.wheel_up {
+                    pending.set(Some(action));
+                }
             },
+            BackgroundLayer{background: background.clone()}
             section{
                 class: "relative x-screen y-screen",
+                style: "{screen_effects.shake_style()}",
                 article{
                     class: "image",
                     if let Some(s) = &story(){
-                        for (index,image) in s.left_img.iter().enumerate() {
+                        for layer in s.layers() {
                             img{
                                 loading: "eager",
-                                key: "{image.name}{image.style}",
-                                class: "bottom-ground {image.class}",
-                                style: "{image.style}left: {index as i32*5-5}rem;z-index:{index};",
-                                src: "{image.name}"
+                                key: "{layer.key}",
+                                class: "bottom-ground {layer.image.class} {highlight(&layer.image).0}",
+                                style: "{layer.image.style}{layer.layout.style(&SPRITE_SLOTS.read())}{highlight(&layer.image).1}{sprite_style(&layer, false)}",
+                                src: "{layer.image.name}"
                             }
                         }
                     }
-                }
-                article{
-                    class: "imgae",
-                    if let Some(s) = &story(){
-                        if let Some(img) = s.center_img.clone(){
-                            img{
-                                loading: "eager",
-                                class: "bottom-ground {img.class}",
-                                style: "{img.style}left: 50%;transform: translateX(-50%);",
-                                src: "{img.name}"
-                            }
-                        }
-                    }
-                }
-                article{
-                    class: "image",
-                    if let Some(s) = &story(){
-                        for (index,image) in s.right_img.iter().enumerate() {
-                            img{
-                                loading: "eager",
-                                key: "{image.name}{image.style}",
-                                class: "bottom-ground {image.class}",
-                                style: "{image.style}right: {index as i32*5-5}rem;z-index:{index};transform: scaleX(-1);",
-                                src: "{image.name}"
-                            }
+                    for layer in exiting() {
+                        img{
+                            loading: "eager",
+                            key: "{layer.key}",
+                            class: "bottom-ground {layer.image.class}",
+                            style: "{layer.image.style}{layer.layout.style(&SPRITE_SLOTS.read())}{sprite_style(&layer, true)}",
+                            src: "{layer.image.name}"
                         }
                     }
                 }
+                {screen_effects.overlays()}
             }
             StoryBox{
                 skip_len: skip_len,
@@ -523,11 +1367,23 @@ pub fn StoryPage(
                 box_style: "",
                 can_skip: true,
                 story: story().map_or_else(|| vec![], |s| s.msg),
+                skippable: skippable(),
+                line_id: story().map_or_else(String::new, |s| s.line_id()),
+                voice: story().and_then(|s| s.voice),
                 on_next: move |_|{
                     on_next.call(DummyData {}); // 여기에 skip_len을 수정하는 로직을 만듦
+                    if let Some(s) = story.peek().as_ref() {
+                        READ_LINES.write().insert(s.line_id());
+                    }
+                    forward.set(true);
                     *story_index.write() += 1;
                 },
-                other_setting: other_setting
+                other_setting: other_setting,
+                save: save,
+                on_load: load,
+                on_rollback: move |_| step_back(),
+                on_jump: jump,
+                on_event: on_event,
             }
         }
     }
@@ -535,6 +1391,27 @@ pub fn StoryPage(
 
 pub struct DummyData {}
 
+/// mouse events this long after a touch came from the touch, miliseconds
+const GHOST_CLICK: u64 = 800;
+
+thread_local! {
+    /// the saved settings are put into TEXTCONFIG by the first StoryPage with SaveSettings
+    static SETTINGS_LOADED: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// stops the default of the keys in data-quick-keys of the focused element, added once
+const QUICK_KEYS_LISTENER: &str = r#"
+if (!window.storyQuickKeys) {
+    window.storyQuickKeys = true;
+    window.addEventListener("keydown", (e) => {
+        const keys = e.target.closest && e.target.closest("[data-quick-keys]");
+        if (keys && keys.dataset.quickKeys.split(" ").includes(e.code)) {
+            e.preventDefault();
+        }
+    });
+}
+"#;
+
 /// The ability to print Vec<TextPrint>
 /// #### A component that is not recommended for direct use.
 /// #### Consider using LightMessageBox
@@ -557,7 +1434,23 @@ pub struct DummyData {}
 ///             story_index: 0,
 ///             other_setting: rsx!{
 ///                 // you can setting to other setting component
-///             }
+///             },
+///             save: SaveSettings::default(), // optional, shows Q.Save and Q.Load
+///             skippable: false, // optional, skip passes this line, see Story::is_skippable
+///             on_load: move |data: SaveData| {
+///                 // optional, quick-load will call this closure with the save, not restored yet
+///             },
+///             on_rollback: move |_| {
+///                 // optional, PageUp will call this closure
+///             },
+///             on_jump: move |snapshot: Snapshot| {
+///                 // optional, shows "jump here" in the log, called with the state of the entry
+///             },
+///             line_id: "prologue-001", // optional, kept in the log entry and StoryEvent::LineStarted
+///             on_event: move |event: StoryEvent| {
+///                 // optional, see StoryEvent
+///             },
+///             voice: "alice-001", // optional, the log can replay it
 ///         }
 ///     }
 /// }
@@ -573,212 +1466,420 @@ fn StoryBox(
     skip_len: usize,
     story_index: usize,
     other_setting: Element,
+    save: Option<SaveSettings>,
+    on_load: Option<EventHandler<SaveData>>,
+    #[props(default)] skippable: bool,
+    on_rollback: Option<EventHandler<()>>,
+    on_jump: Option<EventHandler<Snapshot>>,
+    #[props(default)] line_id: String,
+    voice: Option<String>,
+    on_event: Option<EventHandler<StoryEvent>>,
 ) -> Element {
-    let mut end = use_signal(|| false);
-    let ffward = use_memo(move || TEXTCONFIG().is_ffward);
-    let auto = use_memo(move || TEXTCONFIG().is_auto);
-    let close = use_memo(move || TEXTCONFIG().is_close);
-    let log = use_memo(move || TEXTCONFIG().is_log);
-    let mut text_index = use_signal(|| 0_usize);
+    let emit = move |event: StoryEvent| {
+        if let Some(on_event) = on_event {
+            on_event.call(event);
+        }
+    };
+    let mut state = use_box_state();
+    let ffward = use_memo(move || state.read().is_ffward);
+    let auto = use_memo(move || state.read().is_auto);
+    let close = use_memo(move || state.read().is_close);
+    let log = use_memo(move || state.read().is_log);
+    let setting = use_memo(move || state.read().is_setting);
+    // the settings the typewriter timing depends on
+    let timing = use_memo(move || {
+        let config = TEXTCONFIG.read();
+        (config.speed, config.auto_speed, auto(), ffward())
+    });
     let text_print = use_memo(use_reactive((&story,), |(story,)| story));
     let story_index = use_memo(use_reactive((&story_index,), |(story_index,)| story_index));
-    let mut msg_index = use_signal(|| 0_usize);
-    let mut ffward_hold = use_signal(|| false);
-    let mut ffward_hold_time = use_signal(|| 0_u32);
-    let message_len = use_memo(move || {
-        if let Some(msg) = text_print().get(text_index()) {
-            msg.msg.chars().count()
-        } else {
-            0
-        }
+    let skippable = use_memo(use_reactive((&skippable,), |(skippable,)| skippable));
+    let mut runner = use_signal(|| {
+        StoryRunner::new(text_print.peek().clone(), *story_index.peek())
+            .can_skip(can_skip)
+            .skip_len(skip_len)
     });
+    let text_index = use_memo(move || runner.read().text_index());
+    let msg_index = use_memo(move || runner.read().msg_index());
+    // the browser would scroll and zoom with the gestures
+    let box_style = box_style + "touch-action: none;-webkit-touch-callout: none;";
     let box_style = if close() {
         box_style + "visibility: collapse;"
     } else {
         box_style
     };
 
-    let mut is_skip = use_signal(|| false);
-
-    let before_message = use_memo(move || {
-        text_print()
-            .iter()
-            .take(text_index())
-            .map(|txt| txt.print())
-            .collect::<Vec<Element>>()
-    });
-    let message = use_memo(move || {
-        if let Some(msg) = text_print().get(text_index()) {
-            msg.part_print(msg_index())
-        } else {
-            rsx! {}
+    // characters shown of each part, later parts are laid out hidden
+    let shown = move |index: usize, text: &TextPrint| match index.cmp(&text_index()) {
+        std::cmp::Ordering::Less => text.msg.chars().count(),
+        std::cmp::Ordering::Equal => msg_index(),
+        std::cmp::Ordering::Greater => 0,
+    };
+    use_effect(move || {
+        if let Some(effect) = text_print()
+            .get(text_index())
+            .and_then(|t| t.effect.clone())
+        {
+            effect.trigger();
         }
     });
+    let title_print = use_memo(use_reactive((&title,), |(title,)| title));
+    let log_line = use_memo(use_reactive((&line_id, &voice), |(line_id, voice)| {
+        (line_id, voice)
+    }));
+    let push_log = move || {
+        // the position is only known when a StoryPage is driving this box
+        let snapshot = on_jump.is_some().then(|| {
+            let choice_point = CURRENT_STORY
+                .peek()
+                .as_ref()
+                .is_some_and(|s| s.choice_point);
+            Snapshot::capture(STORY_POSITION.peek().clone(), choice_point)
+        });
+        let (line_id, voice) = log_line();
+        LOG.write().push(
+            LogEntry::new(title_print(), text_print())
+                .line_id(&line_id)
+                .voice(voice)
+                .snapshot(snapshot),
+        );
+    };
     let title: Vec<Element> = title.iter().map(|t| t.print()).collect();
     let auto_clicked = if auto() { "is_clicked" } else { "" };
     let skip_clicked = if ffward() { "is_clicked" } else { "" };
 
-    use_future(move || async move {
-        loop {
-            if TEXTCONFIG().is_setting {
-                wait(5).await;
-            } else if let Some(msg) = text_print().get(text_index()) {
-                *end.write() = false;
-                if is_skip() {
-                    while skip_len > story_index() {
-                        wait(1).await;
-                        on_next.call(DummyData {});
+    let handle = move |event: RunnerEvent| {
+        let index = *story_index.peek();
+        match event {
+            RunnerEvent::CharRevealed { part } => {
+                if let Some(s) = text_print.peek().get(part).and_then(|t| t.sound.clone()) {
+                    if TEXTCONFIG.peek().sound_volum != 0. {
+                        s().play().unwrap();
                     }
                 }
-                if ffward_hold() {
-                    wait(5).await;
-                    *ffward_hold_time.write() += 5;
-                    if ffward_hold_time() > 1000 {
-                        TEXTCONFIG.write().is_ffward = true;
-                    }
+                let chars = runner.peek().visible_chars();
+                emit(StoryEvent::CharRevealed { index, chars });
+            }
+            RunnerEvent::LineCompleted => emit(StoryEvent::LineCompleted { index }),
+            RunnerEvent::Advance(cause) => {
+                // skipped lines are not logged
+                if cause != AdvanceCause::Skip {
+                    push_log();
                 }
-                if ffward() && can_skip
-                // && skip_len > story_index()
-                {
-                    wait(5).await;
-                }
-                // else if skip() {
-                //     TEXTCONFIG.write().is_ffward = false;
-                //     wait((msg.speed as f32 / TEXTCONFIG().speed) as u32).await;
-                // }
-                else {
-                    wait((msg.speed as f32 / TEXTCONFIG().speed) as u32).await;
-                }
-                if !log() {
-                    if message_len() > msg_index() {
-                        *msg_index.write() += 1;
-                        if let Some(s) = &msg.sound {
-                            if TEXTCONFIG().sound_volum != 0. {
-                                s().play().unwrap();
-                            }
-                        }
-                    } else {
-                        *msg_index.write() = 0;
-                        *text_index.write() += 1;
-                    }
+                emit(StoryEvent::Advanced { index, cause });
+                on_next.call(DummyData {});
+            }
+        }
+    };
+    let mut timer = use_signal(TickTimer::default);
+    let active = use_scene_active();
+    // sleeps until the next character or auto advance is due, and ends when nothing is.
+    // wake restarts it when the runner or the timing settings change,
+    // the time it was ended doesn't count
+    let mut typewriter = use_future(move || async move {
+        loop {
+            let elapsed = timer.write().elapsed(CLOCK.peek().now());
+            let config = TEXTCONFIG.peek().clone();
+            let box_state = *state.peek();
+            // a scene hidden under a pushed one waits like an open menu
+            if box_state.is_setting || box_state.is_log || !*active.peek() {
+                timer.write().stop();
+                return;
+            }
+            let (events, due) = {
+                let mut runner = runner.write();
+                runner.set_speed(config.speed, config.auto_speed);
+                runner.set_auto(box_state.is_auto);
+                runner.set_ffward(box_state.is_ffward);
+                let events = runner.tick(elapsed);
+                if runner.is_ffward() != box_state.is_ffward {
+                    state.write().is_ffward = runner.is_ffward();
                 }
-            } else {
-                *end.write() = true;
-                if is_skip() {
-                    while skip_len > story_index() {
-                        wait(1).await;
-                        on_next.call(DummyData {});
-                    }
-                    *is_skip.write() = false;
+                (events, runner.next_due())
+            };
+            for event in events {
+                handle(event);
+            }
+            match due {
+                Some(due) => wait(due.max(1)).await,
+                None => {
+                    timer.write().stop();
+                    return;
                 }
-                if ffward_hold() {
-                    wait(5).await;
-                    *ffward_hold_time.write() += 5;
-                    if ffward_hold_time() > 1000 {
-                        TEXTCONFIG.write().is_ffward = true;
-                    }
-                } else {
-                    wait(10).await;
-                }
-                if ffward() && can_skip
-                // && skip_len > story_index()
-                {
-                    LOG.write().push(text_print().clone());
-                    on_next.call(DummyData {});
-                    *text_index.write() = 0;
-                    *msg_index.write() = 0;
-                } else if ffward() {
-                    TEXTCONFIG.write().is_ffward = false;
-                } else if auto() {
-                    let mut count = 0;
-                    while count < TEXTCONFIG.read().auto_speed && end() && TEXTCONFIG.read().is_auto
-                    {
-                        wait(5).await;
-                        count += 5;
-                    }
-                    if TEXTCONFIG.read().is_auto {
-                        LOG.write().push(text_print().clone());
-                        on_next.call(DummyData {});
-                        *text_index.write() = 0;
-                        *msg_index.write() = 0;
+            }
+        }
+    });
+    let mut wake = move || typewriter.restart();
+    use_effect(move || {
+        timing();
+        setting();
+        log();
+        active();
+        wake();
+    });
+    // the times of two clocks can't be compared, timing starts again with the new one
+    use_effect(move || {
+        CLOCK.read();
+        timer.write().stop();
+        wake();
+    });
+    // the next line, or a quick-load or rollback inside the chapter
+    use_effect(move || {
+        let text = text_print();
+        let index = story_index();
+        let line_id = log_line.peek().0.clone();
+        emit(StoryEvent::LineStarted { index, line_id });
+        runner.write().set_line(text, index);
+        timer.write().stop();
+        wake();
+    });
+    use_effect(move || {
+        let skippable = skippable();
+        runner.write().set_skippable(skippable);
+        wake();
+    });
+    let shortcuts = save.as_ref().is_some_and(|s| s.shortcuts);
+    // F5 would reload the web page and F9 open a menu. The events reach dioxus too late
+    // to stop them, so a listener on the page stops the quick keys of the focused box
+    use_effect(|| {
+        let _ = eval(QUICK_KEYS_LISTENER);
+    });
+    let quick_keys = if shortcuts {
+        let config = TEXTCONFIG.read();
+        let bindings = &config.key_bindings;
+        let mut keys = bindings.keys_of(InputAction::QuickSave);
+        keys.extend(bindings.keys_of(InputAction::QuickLoad));
+        keys.join(" ")
+    } else {
+        String::new()
+    };
+    let quick_save = {
+        let save = save.clone();
+        move || {
+            if let Some(save) = &save {
+                save.quick_save();
+            }
+        }
+    };
+    let quick_load = {
+        let save = save.clone();
+        move || {
+            let Some(save) = &save else {
+                return;
+            };
+            match save.quick_load() {
+                Ok(Some(data)) => {
+                    if let Some(on_load) = on_load {
+                        on_load.call(data);
                     }
                 }
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!("quick load failed: {e}");
+                    emit(StoryEvent::LoadFailed(e));
+                }
+            }
+        }
+    };
+    let mut advance = move || {
+        let event = runner.write().advance();
+        if let Some(event) = event {
+            handle(event);
+        }
+        wake();
+    };
+    // what a key, the wheel or the right button does, a held fast-forward key is done by keydown
+    let run_action = {
+        let quick_save = quick_save.clone();
+        let quick_load = quick_load.clone();
+        move |action: InputAction| match action {
+            InputAction::Advance => advance(),
+            InputAction::FastForward if can_skip => {
+                let ffward = !state.peek().is_ffward;
+                state.write().is_ffward = ffward;
+            }
+            InputAction::Menu => {
+                state.write().is_setting = true;
+                emit(StoryEvent::SettingsOpened);
+            }
+            InputAction::Hide => {
+                let close = !state.peek().is_close;
+                state.write().is_close = close;
             }
+            InputAction::Log => {
+                state.write().is_log = true;
+                emit(StoryEvent::LogOpened);
+            }
+            InputAction::Auto if can_skip => {
+                let auto = !state.peek().is_auto;
+                state.write().is_auto = auto;
+            }
+            InputAction::Rollback => match on_rollback {
+                Some(on_rollback) => on_rollback.call(()),
+                None => {
+                    state.write().is_log = true;
+                    emit(StoryEvent::LogOpened);
+                }
+            },
+            InputAction::QuickSave if shortcuts => quick_save(),
+            InputAction::QuickLoad if shortcuts => quick_load(),
+            _ => {}
+        }
+    };
+    let pending = use_hook(|| try_consume_context::<PendingAction>().map(|p| p.0));
+    let mut pending_action = run_action.clone();
+    use_effect(move || {
+        let Some(mut pending) = pending else {
+            return;
+        };
+        let Some(action) = pending() else {
+            return;
+        };
+        pending.set(None);
+        if !state.peek().is_setting && !state.peek().is_log {
+            pending_action(action);
         }
     });
+    let mut run_action = run_action.clone();
     let keydown = move |e: KeyboardEvent| {
-        if (e.code() == Code::ControlLeft || e.code() == Code::ControlRight) && can_skip
+        let action = TEXTCONFIG.peek().key_bindings.action(&e.code().to_string());
         // && skip_len > story_index()
-        {
-            TEXTCONFIG.write().is_ffward = true;
-        } else {
-            TEXTCONFIG.write().is_ffward = false;
+        let ffward = action == Some(InputAction::FastForward) && can_skip;
+        if state.peek().is_ffward != ffward {
+            state.write().is_ffward = ffward;
+        }
+        match action {
+            Some(InputAction::FastForward) | None => {}
+            Some(action) => run_action(action),
         }
     };
     let keyup = move |e: KeyboardEvent| {
-        if (e.code() == Code::ControlLeft || e.code() == Code::ControlRight) && can_skip {
-            TEXTCONFIG.write().is_ffward = false;
+        let action = TEXTCONFIG.peek().key_bindings.action(&e.code().to_string());
+        if action == Some(InputAction::FastForward) && can_skip {
+            state.write().is_ffward = false;
+        }
+    };
+    let mut touch = use_signal(TouchTracker::default);
+    let mut touch_end = use_signal(|| None::<u64>);
+    // browsers send mouse events and a click after a tap, the touch handlers did the work
+    let from_touch = move || {
+        touch.peek().is_active()
+            || touch_end
+                .peek()
+                .is_some_and(|end| CLOCK.peek().now().saturating_sub(end) < GHOST_CLICK)
+    };
+    let mut release = move || {
+        runner.write().hold(false);
+        if state.peek().is_ffward {
+            state.write().is_ffward = false;
         }
+        wake();
     };
     let click = move |_: MouseEvent| {
-        if end() {
-            LOG.write().push(text_print().clone());
-            on_next.call(DummyData {});
-            *end.write() = false;
-            *text_index.write() = 0;
-            *msg_index.write() = 0;
-        } else if can_skip {
-            *text_index.write() = text_print().len();
+        if !from_touch() {
+            advance();
         }
     };
     let mousedouwn = move |_: MouseEvent| {
+        if from_touch() {
+            return;
+        }
         info!("빨리감기 시도중");
-        *ffward_hold.write() = true;
+        runner.write().hold(true);
+        wake();
     };
     let mouseup = move |_: MouseEvent| {
+        if from_touch() {
+            return;
+        }
         info!("빨리감기 정지 마우스 업");
-        *ffward_hold.write() = false;
-        TEXTCONFIG.write().is_ffward = false;
-        *ffward_hold_time.write() = 0;
+        release();
     };
     let mouseleave = move |_: MouseEvent| {
+        if from_touch() {
+            return;
+        }
         info!("빨리감기 정지 마우스 아웃");
-        *ffward_hold.write() = false;
-        TEXTCONFIG.write().is_ffward = false;
-        *ffward_hold_time.write() = 0;
+        release();
+    };
+    let points = |e: &TouchEvent| {
+        e.touches()
+            .iter()
+            .map(|t| {
+                let point = t.client_coordinates();
+                (point.x, point.y)
+            })
+            .collect::<Vec<_>>()
+    };
+    let touchstart = move |e: TouchEvent| {
+        touch.write().start(&points(&e), CLOCK.peek().now());
+        // a long press is the same hold as the mouse
+        let press = touch.peek().is_press();
+        runner.write().hold(press);
+        if !press && state.peek().is_ffward {
+            state.write().is_ffward = false;
+        }
+        wake();
+    };
+    let touchmove = move |e: TouchEvent| {
+        let was_press = touch.peek().is_press();
+        touch.write().moved(&points(&e));
+        if was_press && !touch.peek().is_press() {
+            release();
+        }
+    };
+    let touchend = move |e: TouchEvent| {
+        let now = CLOCK.peek().now();
+        let remaining = e.touches().len();
+        let gesture = touch.write().end(remaining, now);
+        if remaining > 0 {
+            return;
+        }
+        touch_end.set(Some(now));
+        release();
+        match gesture {
+            Some(Gesture::Tap) => advance(),
+            Some(Gesture::SwipeDown) => {
+                state.write().is_log = true;
+                emit(StoryEvent::LogOpened);
+            }
+            Some(Gesture::TwoFingerTap) => {
+                state.write().is_close = true;
+            }
+            None => {}
+        }
+    };
+    let touchcancel = move |_: TouchEvent| {
+        touch.write().cancel();
+        touch_end.set(Some(CLOCK.peek().now()));
+        release();
     };
     rsx! {
-        if TEXTCONFIG().is_setting{
+        if setting(){
             Setting{other: other_setting}
         }else if log() {
-            section {
-                class: "message-log",
-                for l in LOG().iter().rev(){
-                    article{
-                        for text in l.iter(){
-                            {text.print()}
-                        }
-                    }
-                }
-                nav{
-                    class: "msg-log-button",
-                    onclick: move |e| {
-                        TEXTCONFIG.write().is_log = false;
-                        e.stop_propagation();
-                    },
-                    "exit"
-                }
-            }
+            MessageLogView{on_jump: on_jump}
         } else {
             article{
                 class: "{box_class}",
                 style: "{box_style}",
                 onkeydown: keydown,
                 onkeyup: keyup,
+                "data-quick-keys": "{quick_keys}",
                 onclick: click,
                 onmousedown: mousedouwn,
                 onmouseup: mouseup,
                 onmouseleave: mouseleave,
+                ontouchstart: touchstart,
+                ontouchmove: touchmove,
+                ontouchend: touchend,
+                ontouchcancel: touchcancel,
+                // a long press opens the context menu on some phones
+                oncontextmenu: move |e| {
+                    if from_touch() {
+                        e.stop_propagation();
+                    }
+                },
                 tabindex: 1,
                 autofocus: true,
                 article{
@@ -790,10 +1891,13 @@ fn StoryBox(
                             }
                         }
                     }
-                    for t in before_message().iter(){
-                        {t}
+                    for (index, text) in text_print().into_iter().enumerate(){
+                        TypedText{
+                            key: "{story_index}-{index}",
+                            shown: shown(index, &text),
+                            text: text,
+                        }
                     }
-                    {message}
                 }
             }
             if !close(){
@@ -802,7 +1906,8 @@ fn StoryBox(
                     span{
                         class:"msg-log-span",
                         onclick: move |e|{
-                            TEXTCONFIG.write().is_log = true;
+                            state.write().is_log = true;
+                            emit(StoryEvent::LogOpened);
                             e.stop_propagation();
                         },
                         "log"
@@ -811,8 +1916,8 @@ fn StoryBox(
                         span{
                             class:"{auto_clicked} msg-auto-span",
                             onclick: move |e|{
-                                let auto = !TEXTCONFIG.read().is_auto;
-                                TEXTCONFIG.write().is_auto = auto;
+                                let auto = !state.read().is_auto;
+                                state.write().is_auto = auto;
                                 e.stop_propagation();
                             },
                             "auto"
@@ -823,16 +1928,41 @@ fn StoryBox(
                                 // let skip = !TEXTCONFIG.read().is_ffward;
                                 // TEXTCONFIG.write().is_ffward = skip;
                                 info!("스킵 시도");
-                                *is_skip.write() = true;
+                                runner.write().skip();
+                                wake();
                                 e.stop_propagation();
                             },
                             "skip"
                         }
                     }
+                    if save.is_some(){
+                        span{
+                            class:"msg-qsave-span",
+                            onclick: {
+                                let quick_save = quick_save.clone();
+                                move |e: MouseEvent|{
+                                    quick_save();
+                                    e.stop_propagation();
+                                }
+                            },
+                            "Q.Save"
+                        }
+                        span{
+                            class:"msg-qload-span",
+                            onclick: {
+                                let quick_load = quick_load.clone();
+                                move |e: MouseEvent|{
+                                    quick_load();
+                                    e.stop_propagation();
+                                }
+                            },
+                            "Q.Load"
+                        }
+                    }
                     span{
                         class:"msg-close-span",
                         onclick: move |e|{
-                            TEXTCONFIG.write().is_close = true;
+                            state.write().is_close = true;
                             e.stop_propagation();
                         },
                         "close"
@@ -840,7 +1970,8 @@ fn StoryBox(
                     span{
                         class:"msg-setting-span",
                         onclick: move |e|{
-                            TEXTCONFIG.write().is_setting = true;
+                            state.write().is_setting = true;
+                            emit(StoryEvent::SettingsOpened);
                             e.stop_propagation();
                         },
                         "setting"
@@ -851,8 +1982,51 @@ fn StoryBox(
     }
 }
 
+/// what LightMessageBox does after its last line
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SequenceEnd {
+    /// show nothing until storys changes
+    #[default]
+    Hide,
+    /// keep the last line on screen, clicks do nothing
+    Stay,
+    /// start again from skip
+    Loop,
+}
+
+/// what LightMessageBox shows after a line is left
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SequenceStep {
+    Next(usize),
+    /// the last line was left, the rest is what SequenceEnd says
+    Hide,
+    Stay,
+    Restart(usize),
+}
+
+fn sequence_step(index: usize, len: usize, skip: usize, end: SequenceEnd) -> SequenceStep {
+    if index + 1 < len {
+        return SequenceStep::Next(index + 1);
+    }
+    match end {
+        SequenceEnd::Stay if index < len => SequenceStep::Stay,
+        SequenceEnd::Loop if skip < len => SequenceStep::Restart(skip),
+        _ => SequenceStep::Hide,
+    }
+}
+
+/// where a LightMessageBox is in the storys and skip it was given
+#[derive(Clone, PartialEq, Debug)]
+struct SequenceProgress {
+    storys: Vec<Story>,
+    skip: usize,
+    index: usize,
+    finished: bool,
+}
+
 /// Implement small speech balloons, etc. A lightweight version of StoryPage
-/// Image output is not supported.
+/// Image output is not supported. Nothing is shown when storys is empty,
+/// and the box starts again at skip when storys or skip changes.
 /// # Example
 /// ```
 /// #[component]
@@ -866,6 +2040,12 @@ fn StoryBox(
 ///              box_class: "box-class", // you can setting to box class
 ///              other_setting: rsx!{}, // you can add other setting component
 ///              can_skip: false, // You can set whether skip is possible or not
+///              on_event: move |event: StoryEvent| {}, // optional, see StoryEvent
+///              end: SequenceEnd::Loop, // optional, Hide by default
+///              chapter: "prologue", // optional, line ids match the StoryPage of the chapter
+///              on_finish: move |_| {
+///                  // optional, called each time the last line is left
+///              },
 ///         }
 ///     }
 /// }
@@ -881,30 +2061,104 @@ pub fn LightMessageBox(
     other_setting: Element,
     skip: usize,
     on_next: EventHandler<DummyData>,
+    on_event: Option<EventHandler<StoryEvent>>,
+    #[props(default)] end: SequenceEnd,
+    on_finish: Option<EventHandler<()>>,
+    #[props(default)] chapter: String,
 ) -> Element {
-    let mut story_index = use_signal(|| skip);
+    use_context_provider(|| Signal::new(BoxState::default()));
+    let with_ids = use_memo(use_reactive((&chapter, &storys), |(chapter, storys)| {
+        assign_line_ids(&chapter, storys.clone()).unwrap_or_else(|e| {
+            tracing::warn!("{e}");
+            storys
+        })
+    }));
+    // None until the first line is left
+    let mut progress = use_signal(|| None::<SequenceProgress>);
+    // a loop over a single line needs a new StoryBox to show it again
+    let mut rounds = use_signal(|| 0_usize);
+    // the progress of other storys or skip doesn't count, the box starts at skip
+    let (story_index, finished) = match &*progress.read() {
+        Some(p) if p.storys == storys && p.skip == skip => (p.index, p.finished),
+        _ => (skip, false),
+    };
+    let len = storys.len();
+    // an empty storys or a skip past the end shows nothing
+    let story = with_ids.read().get(story_index).cloned()?;
+    if finished {
+        return rsx! {};
+    }
+    let line_id = story.line_id();
     rsx! {
         StoryBox{
+            key: "{rounds}",
             skip_len: skip_len,
-            title: storys[story_index()].title.clone(),
-            story: storys[story_index()].msg.clone(),
+            title: story.title.clone(),
+            story: story.msg.clone(),
             box_style: box_style,
             can_skip: can_skip,
             box_class: box_class,
+            skippable: story.is_skippable(),
+            line_id: line_id.clone(),
+            voice: story.voice.clone(),
             on_next: move |_| {
                 on_next.call(DummyData{});
-                *story_index.write() += 1;
+                READ_LINES.write().insert(line_id.clone());
+                let step = sequence_step(story_index, len, skip, end);
+                let (index, finished) = match step {
+                    SequenceStep::Next(index) => (index, false),
+                    SequenceStep::Hide => (story_index, true),
+                    SequenceStep::Stay => (story_index, false),
+                    SequenceStep::Restart(index) => {
+                        *rounds.write() += 1;
+                        (index, false)
+                    }
+                };
+                progress.set(Some(SequenceProgress {
+                    storys: storys.clone(),
+                    skip,
+                    index,
+                    finished,
+                }));
+                if matches!(step, SequenceStep::Next(_)) {
+                    return;
+                }
+                if let Some(on_event) = on_event {
+                    on_event.call(StoryEvent::Finished);
+                }
+                if let Some(on_finish) = on_finish {
+                    on_finish.call(());
+                }
             },
-            story_index: story_index.read().clone(),
+            on_event: on_event,
+            story_index: story_index,
             other_setting: other_setting
         }
     }
 }
 #[component]
 pub fn Setting(other: Element) -> Element {
+    let mut state = use_box_state();
+    // the action waiting for a key
+    let mut rebinding = use_signal(|| None::<InputAction>);
+    let keys = move |action: InputAction| TEXTCONFIG.read().key_bindings.keys_of(action).join(", ");
     rsx! {
             section{
                 class: "textconfig",
+                tabindex: 0,
+                onkeydown: move |e| {
+                    if let Some(action) = rebinding() {
+                        // Escape cancels, it can't be bound here
+                        if e.code() != Code::Escape {
+                            TEXTCONFIG
+                                .write()
+                                .key_bindings
+                                .rebind(action, &e.code().to_string());
+                        }
+                        rebinding.set(None);
+                        e.stop_propagation();
+                    }
+                },
                 label{"Music Volume: {TEXTCONFIG.read().music_volum}" }
                 input{
                     r#type: "range",
@@ -953,12 +2207,67 @@ pub fn Setting(other: Element) -> Element {
                         e.stop_propagation();
                     }
                 }
+                label{"Skip Unread Text" }
+                input{
+                    r#type: "checkbox",
+                    checked: TEXTCONFIG.read().skip_unread,
+                    onchange: move |e|{
+                        TEXTCONFIG.write().skip_unread = e.data.checked();
+                        e.stop_propagation();
+                    }
+                }
+                label{"Reduced Motion" }
+                input{
+                    r#type: "checkbox",
+                    checked: TEXTCONFIG.read().reduced_motion,
+                    onchange: move |e|{
+                        TEXTCONFIG.write().reduced_motion = e.data.checked();
+                        e.stop_propagation();
+                    }
+                }
+                label{"Key Bindings"}
+                for action in InputAction::ALL {
+                    div{
+                        class: "key-binding",
+                        span{"{action}"}
+                        button{
+                            onclick: move |e|{
+                                rebinding.set(Some(action));
+                                e.stop_propagation();
+                            },
+                            if rebinding() == Some(action) {
+                                "press a key, Escape cancels"
+                            } else {
+                                "{keys(action)}"
+                            }
+                        }
+                    }
+                }
+                PointerBinding{
+                    name: "Wheel Up",
+                    action: TEXTCONFIG.read().key_bindings.wheel_up,
+                    on_change: move |action| TEXTCONFIG.write().key_bindings.wheel_up = action,
+                }
+                PointerBinding{
+                    name: "Right Click",
+                    action: TEXTCONFIG.read().key_bindings.right_click,
+                    on_change: move |action| TEXTCONFIG.write().key_bindings.right_click = action,
+                }
+                button{
+                    class: "key-binding-reset",
+                    onclick: move |e|{
+                        TEXTCONFIG.write().key_bindings = KeyBindings::default();
+                        rebinding.set(None);
+                        e.stop_propagation();
+                    },
+                    "Reset Keys"
+                }
                 {other}
                 div{class: "setting-close"}
                 nav{
                     class: "setting-close",
                     onclick: move |e|{
-                        TEXTCONFIG.write().is_setting = false;
+                        state.write().is_setting = false;
                         e.stop_propagation();
                     },
                     "exit"
@@ -967,3 +2276,117 @@ pub fn Setting(other: Element) -> Element {
 
     }
 }
+
+/// the action of the wheel or the right button in Setting
+#[component]
+fn PointerBinding(
+    name: String,
+    action: Option<InputAction>,
+    on_change: EventHandler<Option<InputAction>>,
+) -> Element {
+    rsx! {
+        div{
+            class: "key-binding",
+            span{"{name}"}
+            select{
+                onchange: move |e| {
+                    // the value is the index in InputAction::ALL, empty for none
+                    let action = e
+                        .value()
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|i| InputAction::ALL.get(i).copied());
+                    on_change.call(action);
+                    e.stop_propagation();
+                },
+                option{ value: "", selected: action.is_none(), "None" }
+                for (i, a) in InputAction::ALL.into_iter().enumerate() {
+                    option{ value: "{i}", selected: action == Some(a), "{a}" }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        assign_line_ids, derive_line_id, sequence_step, DuplicateLineId, SequenceEnd, SequenceStep,
+        Story,
+    };
+    use crate::text_print::TextPrint;
+
+    #[test]
+    fn story_line_ids() {
+        let line = |msg: &str| Story::default().msg(TextPrint::parse(format!("{{{{}}}}{msg}")));
+        let storys = assign_line_ids(
+            "prologue",
+            vec![line("..."), line("hello").id("hello"), line("...")],
+        )
+        .unwrap();
+        assert_eq!(storys[1].line_id(), "hello");
+        assert_eq!(storys[2].line_id(), format!("{}-2", storys[0].line_id()));
+        assert_ne!(storys[0].line_id(), line("...").line_id());
+
+        let duplicate = assign_line_ids("prologue", vec![line("a").id("x"), line("b").id("x")]);
+        assert_eq!(
+            duplicate.unwrap_err(),
+            DuplicateLineId {
+                id: "x".to_owned(),
+                first: 0,
+                second: 1
+            }
+        );
+    }
+
+    #[test]
+    fn derive_line_id_stable() {
+        // make_story_file emits the same value for these inputs
+        assert_eq!(
+            derive_line_id("prologue", "alice", "hello"),
+            "f2ae65c5bee0729c"
+        );
+    }
+
+    #[test]
+    fn light_message_box_sequence() {
+        assert_eq!(
+            sequence_step(0, 3, 0, SequenceEnd::Hide),
+            SequenceStep::Next(1)
+        );
+        assert_eq!(
+            sequence_step(2, 3, 0, SequenceEnd::Hide),
+            SequenceStep::Hide
+        );
+        assert_eq!(
+            sequence_step(2, 3, 0, SequenceEnd::Stay),
+            SequenceStep::Stay
+        );
+        assert_eq!(
+            sequence_step(2, 3, 1, SequenceEnd::Loop),
+            SequenceStep::Restart(1)
+        );
+        // a single line loops to itself
+        assert_eq!(
+            sequence_step(0, 1, 0, SequenceEnd::Loop),
+            SequenceStep::Restart(0)
+        );
+        // empty storys and a skip past the end show nothing
+        assert_eq!(
+            sequence_step(0, 0, 0, SequenceEnd::Stay),
+            SequenceStep::Hide
+        );
+        assert_eq!(
+            sequence_step(0, 0, 0, SequenceEnd::Loop),
+            SequenceStep::Hide
+        );
+        assert_eq!(
+            sequence_step(3, 3, 3, SequenceEnd::Loop),
+            SequenceStep::Hide
+        );
+        assert_eq!(
+            sequence_step(3, 3, 3, SequenceEnd::Stay),
+            SequenceStep::Hide
+        );
+    }
+}
@@ -6,10 +6,12 @@ use std::{
 
 use dioxus::prelude::*;
 use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
 
+use crate::screen_effect::ScreenEffect;
 use crate::sound_effect::{SoundEffect, SOUND_EFFECTS};
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum TextOption {
     Normal,
     Italic,
@@ -30,7 +32,7 @@ impl Display for TextOption {
         }
     }
 }
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum FontWeight {
     Normal,
     Bold,
@@ -62,6 +64,8 @@ pub struct TextPrint {
     pub font_weight: FontWeight,
     pub class: String,
     pub sound: Option<Rc<dyn Fn() -> SoundEffect>>,
+    /// played when this part starts printing
+    pub effect: Option<ScreenEffect>,
 }
 impl PartialEq for TextPrint {
     fn eq(&self, other: &Self) -> bool {
@@ -72,6 +76,7 @@ impl PartialEq for TextPrint {
             && self.font == other.font
             && self.is_split == other.is_split
             && self.class == other.class
+            && self.effect == other.effect
     }
 }
 impl Debug for TextPrint {
@@ -93,6 +98,7 @@ impl Default for TextPrint {
             font_weight: FontWeight::Normal,
             class: "".to_owned(),
             sound: None,
+            effect: None,
         }
     }
 }
@@ -143,6 +149,10 @@ impl TextPrint {
         self.is_split = is_split;
         self
     }
+    pub fn effect(mut self, effect: ScreenEffect) -> Self {
+        self.effect = Some(effect);
+        self
+    }
     pub fn color_bold(msg: &str, color: &str) -> Self {
         TextPrint::default()
             .msg(msg)
@@ -178,6 +188,7 @@ impl TextPrint {
             font_weight,
             class,
             sound: None,
+            effect: None,
         }
     }
     fn global_print(text: &TextPrint) -> Element {
@@ -234,6 +245,31 @@ impl TextPrint {
         TextPrint::global_print(&self_temp)
     }
 
+    /// print with the parts matching query wrapped in mark, for log search
+    pub fn highlight_print(&self, query: &str) -> Element {
+        rsx! {
+            span{
+                class: "{self.class}",
+                style: r#"font-style: {self.option};font-size: {self.size}rem;font-family: "{self.font}";color: {self.color};font-weight: {self.font_weight};{(self.style)()}"#,
+                for (index, line) in self.msg.split('\n').enumerate() {
+                    if index > 0 {
+                        br{}
+                    }
+                    for (piece, hit) in split_matches(line, query) {
+                        if hit {
+                            mark{
+                                class: "msg-log-mark",
+                                "{piece}"
+                            }
+                        } else {
+                            "{piece}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     pub fn parse(s: String) -> Vec<TextPrint> {
         let mut textprint = TextPrint::default();
         let mut s: VecDeque<&str> = s.split("{{").skip(1).collect();
@@ -246,6 +282,8 @@ impl TextPrint {
             let message = s.next().unwrap();
 
             textprint = textprint.msg(message);
+            // effects play once, they are not carried to the next part
+            textprint.effect = None;
 
             while let Some(op) = option.next() {
                 if op == "" {
@@ -378,6 +416,9 @@ impl TextPrint {
                     "class" => {
                         textprint = textprint.class(value);
                     }
+                    "effect" => {
+                        textprint.effect = ScreenEffect::parse(value);
+                    }
                     "is_split" => {
                         let v = if value.trim() == "true" { true } else { false };
                         textprint = textprint.is_split(v);
@@ -391,9 +432,140 @@ impl TextPrint {
     }
 }
 
+/// The whole TextPrint rendered once, characters from shown on are hidden with visibility.
+/// Split text keeps one span per character with its own style, made once per text.
+/// ## Example
+/// ```
+/// #[component]
+/// fn TestTypedText(text: TextPrint) -> Element {
+///     rsx! {
+///         TypedText{text: text, shown: 3}
+///     }
+/// }
+/// ```
+#[component]
+pub fn TypedText(text: TextPrint, shown: usize) -> Element {
+    // the style of a split text is random per character, it must not change on a reveal
+    let char_styles = use_memo(use_reactive((&text,), |(text,)| {
+        if text.is_split {
+            text.msg.chars().map(|_| (text.style)()).collect::<Vec<_>>()
+        } else {
+            vec![]
+        }
+    }));
+    let outer_style = use_memo(use_reactive((&text,), |(text,)| {
+        if text.is_split {
+            "".to_owned()
+        } else {
+            (text.style)()
+        }
+    }));
+    let outer_class = if text.is_split {
+        ""
+    } else {
+        text.class.as_str()
+    };
+    let hidden = |index: usize| {
+        if index < shown {
+            ""
+        } else {
+            "visibility: hidden;"
+        }
+    };
+    let style = |index: usize| {
+        let styles = char_styles.read();
+        format!(
+            "{}{}",
+            styles.get(index).map_or("", |s| s.as_str()),
+            hidden(index)
+        )
+    };
+    rsx! {
+        span{
+            class: "{outer_class}",
+            style: r#"font-style: {text.option};font-size: {text.size}rem;font-family: "{text.font}";color: {text.color};font-weight: {text.font_weight};{outer_style}"#,
+            if text.is_split {
+                for (index, ch) in text.msg.chars().enumerate() {
+                    span{
+                        key: "{index}",
+                        class: "{text.class}",
+                        style: "{style(index)}",
+                        if ch == '\n' {
+                            br{}
+                        } else {
+                            "{ch}"
+                        }
+                    }
+                }
+            } else {
+                for (index, (shown, rest)) in split_shown(&text.msg, shown).into_iter().enumerate() {
+                    if index > 0 {
+                        br{}
+                    }
+                    "{shown}"
+                    if !rest.is_empty() {
+                        span{
+                            style: "visibility: hidden;",
+                            "{rest}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// each line of msg cut into the shown characters and the rest, a line break counts as one
+pub(crate) fn split_shown(msg: &str, shown: usize) -> Vec<(String, String)> {
+    let mut left = shown;
+    msg.split('\n')
+        .map(|line| {
+            let count = line.chars().count();
+            let cut = line
+                .char_indices()
+                .nth(left.min(count))
+                .map_or(line.len(), |(i, _)| i);
+            left = left.saturating_sub(count + 1);
+            (line[..cut].to_owned(), line[cut..].to_owned())
+        })
+        .collect()
+}
+
+/// the text cut into pieces that match query or not, ignoring case
+pub(crate) fn split_matches(text: &str, query: &str) -> Vec<(String, bool)> {
+    let lower = text.to_lowercase();
+    // lowercasing can change the byte length, then the case has to match
+    let (haystack, needle) = if lower.len() == text.len() {
+        (lower, query.to_lowercase())
+    } else {
+        (text.to_owned(), query.to_owned())
+    };
+    if needle.is_empty() {
+        return vec![(text.to_owned(), false)];
+    }
+    let mut pieces = vec![];
+    let mut last = 0;
+    for (start, found) in haystack.match_indices(&needle) {
+        let end = start + found.len();
+        let (Some(before), Some(hit)) = (text.get(last..start), text.get(start..end)) else {
+            return vec![(text.to_owned(), false)];
+        };
+        if !before.is_empty() {
+            pieces.push((before.to_owned(), false));
+        }
+        pieces.push((hit.to_owned(), true));
+        last = end;
+    }
+    if last < text.len() {
+        pieces.push((text[last..].to_owned(), false));
+    }
+    pieces
+}
+
 #[cfg(test)]
 mod tests {
-    use super::TextPrint;
+    use super::{split_matches, split_shown, TextPrint};
+    use crate::screen_effect::ScreenEffect;
 
     #[test]
     fn text_print_parse() {
@@ -414,4 +586,45 @@ mod tests {
         assert_eq!(text_parse[2], text_vec[2]);
         assert_eq!(text_parse, text_vec);
     }
+
+    #[test]
+    fn text_print_parse_effect() {
+        let message = "{{effect:shake(5)}}쾅!{{color:red}}메세지".to_owned();
+        let text_parse = TextPrint::parse(message);
+
+        assert_eq!(text_parse[0].effect, Some(ScreenEffect::shake(5.)));
+        assert_eq!(text_parse[1].effect, None);
+    }
+
+    #[test]
+    fn text_print_split_matches() {
+        assert_eq!(
+            split_matches("Hello hello", "hello"),
+            vec![
+                ("Hello".to_owned(), true),
+                (" ".to_owned(), false),
+                ("hello".to_owned(), true)
+            ]
+        );
+        assert_eq!(
+            split_matches("안녕하세요", "하세"),
+            vec![
+                ("안녕".to_owned(), false),
+                ("하세".to_owned(), true),
+                ("요".to_owned(), false)
+            ]
+        );
+        assert_eq!(split_matches("text", ""), vec![("text".to_owned(), false)]);
+    }
+
+    #[test]
+    fn text_print_split_shown() {
+        let pair = |a: &str, b: &str| (a.to_owned(), b.to_owned());
+        assert_eq!(
+            split_shown("ab\ncd", 4),
+            vec![pair("ab", ""), pair("c", "d")]
+        );
+        assert_eq!(split_shown("안녕", 1), vec![pair("안", "녕")]);
+        assert_eq!(split_shown("ab", 0), vec![pair("", "ab")]);
+    }
 }
@@ -0,0 +1,251 @@
+use crate::message_log::MessageLog;
+use crate::story_base::Story;
+use crate::text_print::{FontWeight, TextOption, TextPrint};
+
+/// output of story_transcript and log_transcript
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TranscriptFormat {
+    /// `speaker: message` lines
+    Text,
+    /// bold speakers, bold and italic text kept
+    Markdown,
+    /// a standalone page keeping color, weight and italic
+    Html,
+}
+
+struct Line<'a> {
+    speaker: String,
+    msg: &'a [TextPrint],
+    choice: Option<&'a str>,
+}
+
+/// a transcript of the storys for proofreading, it needs no UI
+/// # Example
+/// ```
+/// fn test(storys: Vec<Story>) {
+///     let html = story_transcript("Prologue", &storys, TranscriptFormat::Html);
+///     std::fs::write("prologue.html", html).unwrap();
+/// }
+/// ```
+pub fn story_transcript(title: &str, storys: &[Story], format: TranscriptFormat) -> String {
+    let lines = storys
+        .iter()
+        .map(|s| Line {
+            speaker: plain(&s.title),
+            msg: &s.msg,
+            choice: None,
+        })
+        .collect::<Vec<_>>();
+    render(title, &lines, format)
+}
+
+/// a transcript of the messages in the log with the picked choices
+/// # Example
+/// ```
+/// fn test() {
+///     let text = log_transcript("Log", &LOG.read(), TranscriptFormat::Text);
+/// }
+/// ```
+pub fn log_transcript(title: &str, log: &MessageLog, format: TranscriptFormat) -> String {
+    let lines = log
+        .iter()
+        .map(|e| Line {
+            speaker: e.speaker(),
+            msg: &e.msg,
+            choice: e.choice.as_deref(),
+        })
+        .collect::<Vec<_>>();
+    render(title, &lines, format)
+}
+
+fn plain(texts: &[TextPrint]) -> String {
+    texts
+        .iter()
+        .map(|t| t.msg.as_str())
+        .collect::<String>()
+        .trim()
+        .to_owned()
+}
+
+fn render(title: &str, lines: &[Line], format: TranscriptFormat) -> String {
+    match format {
+        TranscriptFormat::Text => render_text(title, lines),
+        TranscriptFormat::Markdown => render_markdown(title, lines),
+        TranscriptFormat::Html => render_html(title, lines),
+    }
+}
+
+fn render_text(title: &str, lines: &[Line]) -> String {
+    let mut out = format!("{title}\n\n");
+    for line in lines {
+        let msg = plain(line.msg);
+        if line.speaker.is_empty() {
+            out.push_str(&format!("{msg}\n"));
+        } else {
+            out.push_str(&format!("{}: {msg}\n", line.speaker));
+        }
+        if let Some(choice) = line.choice {
+            out.push_str(&format!("> {choice}\n"));
+        }
+    }
+    out
+}
+
+fn is_bold(weight: FontWeight) -> bool {
+    match weight {
+        FontWeight::Bold => true,
+        FontWeight::Num(weight) => weight >= 600,
+        FontWeight::Normal => false,
+    }
+}
+
+fn escape_markdown(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn render_markdown(title: &str, lines: &[Line]) -> String {
+    let mut out = format!("# {}\n\n", escape_markdown(title));
+    for line in lines {
+        if !line.speaker.is_empty() {
+            out.push_str(&format!("**{}**: ", escape_markdown(&line.speaker)));
+        }
+        for text in line.msg {
+            let msg = text.msg.replace('\n', " ");
+            let trimmed = msg.trim();
+            if trimmed.is_empty() {
+                out.push_str(&msg);
+                continue;
+            }
+            let mut marker = String::new();
+            if is_bold(text.font_weight) {
+                marker.push_str("**");
+            }
+            if text.option != TextOption::Normal {
+                marker.push('*');
+            }
+            // the markers have to touch the text, the spaces go outside
+            let start = msg.len() - msg.trim_start().len();
+            let end = msg.trim_end().len();
+            out.push_str(&format!(
+                "{}{marker}{}{marker}{}",
+                &msg[..start],
+                escape_markdown(trimmed),
+                &msg[end..]
+            ));
+        }
+        out.push_str("\n\n");
+        if let Some(choice) = line.choice {
+            out.push_str(&format!("> {}\n\n", escape_markdown(choice)));
+        }
+    }
+    out
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_html(title: &str, lines: &[Line]) -> String {
+    let title = escape_html(title);
+    let mut out = format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ max-width: 48rem; margin: 2rem auto; font-family: sans-serif; line-height: 1.6; }}
+.speaker {{ font-weight: bold; margin-right: 0.5rem; }}
+.choice {{ margin-left: 2rem; color: gray; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+"#
+    );
+    for line in lines {
+        out.push_str("<p class=\"line\">");
+        if !line.speaker.is_empty() {
+            out.push_str(&format!(
+                "<span class=\"speaker\">{}</span>",
+                escape_html(&line.speaker)
+            ));
+        }
+        for text in line.msg {
+            out.push_str(&format!(
+                "<span style=\"color: {};font-weight: {};font-style: {};\">{}</span>",
+                escape_html(&text.color),
+                text.font_weight,
+                text.option,
+                escape_html(&text.msg).replace('\n', "<br>")
+            ));
+        }
+        out.push_str("</p>\n");
+        if let Some(choice) = line.choice {
+            out.push_str(&format!(
+                "<p class=\"choice\">&gt; {}</p>\n",
+                escape_html(choice)
+            ));
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{log_transcript, story_transcript, TranscriptFormat};
+    use crate::message_log::{LogEntry, MessageLog};
+    use crate::story_base::Story;
+    use crate::text_print::TextPrint;
+
+    fn storys() -> Vec<Story> {
+        vec![
+            Story::default().msg(TextPrint::parse("{{}}It was raining.".to_owned())),
+            Story::default()
+                .title(TextPrint::parse("{{}}Alice".to_owned()))
+                .msg(TextPrint::parse(
+                    "{{}}I said {{font_weight:bold|color:red}}no <way>{{default:}}!".to_owned(),
+                )),
+        ]
+    }
+
+    #[test]
+    fn transcript_formats() {
+        assert_eq!(
+            story_transcript("Prologue", &storys(), TranscriptFormat::Text),
+            "Prologue\n\nIt was raining.\nAlice: I said no <way>!\n"
+        );
+        assert_eq!(
+            story_transcript("Prologue", &storys(), TranscriptFormat::Markdown),
+            "# Prologue\n\nIt was raining.\n\n**Alice**: I said **no \\<way\\>**!\n\n"
+        );
+        let html = story_transcript("Prologue", &storys(), TranscriptFormat::Html);
+        assert!(html.contains(r#"<span class="speaker">Alice</span>"#));
+        assert!(html.contains(
+            r#"<span style="color: red;font-weight: bold;font-style: normal;">no &lt;way&gt;</span>"#
+        ));
+    }
+
+    #[test]
+    fn log_transcript_choice() {
+        let mut log = MessageLog::default();
+        log.push(
+            LogEntry::new(vec![], vec![TextPrint::default().msg("Where to?")]).choice("Library"),
+        );
+        assert_eq!(
+            log_transcript("Log", &log, TranscriptFormat::Text),
+            "Log\n\nWhere to?\n> Library\n"
+        );
+    }
+}
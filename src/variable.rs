@@ -0,0 +1,56 @@
+use std::collections::BTreeMap;
+
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Global variables for story flags and counters, saved with the game
+pub static VARIABLES: GlobalSignal<BTreeMap<String, Variable>> = Signal::global(BTreeMap::new);
+
+/// a value stored in VARIABLES
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum Variable {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+impl From<bool> for Variable {
+    fn from(value: bool) -> Self {
+        Variable::Bool(value)
+    }
+}
+impl From<i64> for Variable {
+    fn from(value: i64) -> Self {
+        Variable::Int(value)
+    }
+}
+impl From<f64> for Variable {
+    fn from(value: f64) -> Self {
+        Variable::Float(value)
+    }
+}
+impl From<&str> for Variable {
+    fn from(value: &str) -> Self {
+        Variable::Text(value.to_owned())
+    }
+}
+
+/// set story variable
+/// # Example
+/// ```
+/// fn test() {
+///     set_var("met_alice", true);
+///     assert_eq!(get_var("met_alice"), Some(Variable::Bool(true)));
+/// }
+/// ```
+pub fn set_var(name: &str, value: impl Into<Variable>) {
+    VARIABLES.write().insert(name.to_owned(), value.into());
+}
+/// get story variable
+pub fn get_var(name: &str) -> Option<Variable> {
+    VARIABLES.read().get(name).cloned()
+}
+/// remove story variable
+pub fn remove_var(name: &str) -> Option<Variable> {
+    VARIABLES.write().remove(name)
+}